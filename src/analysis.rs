@@ -0,0 +1,528 @@
+//! Groups the dependencies declared by the workspaces of a monorepo, the
+//! engine behind the default check and `search`.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use monocheck::analysis::{Analysis, Options};
+//! use monocheck::monorepo::Monorepo;
+//!
+//! let monorepo = Monorepo::discover(Path::new("."))?;
+//! let analysis = Analysis::run(&monorepo, &Options::default())?;
+//!
+//! for entry in analysis.duplicates.duplicated() {
+//!     println!("{} is declared by {} workspaces", entry.name, entry.count);
+//! }
+//! # Ok::<(), monocheck::monorepo::MonorepoError>(())
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::str::FromStr;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::git;
+use crate::models::package_json::{PackageJson, Section};
+use crate::models::resolver::Resolver;
+use crate::models::semantic_version::{SemanticVersion, VersionRange};
+use crate::models::spec::{normalize_path, Spec, SpecKind};
+use crate::monorepo::{Monorepo, Result};
+use crate::report::{DuplicateEntry, Duplicates, Search, SearchMatch, Usage};
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct Dependency {
+    pub versions: HashSet<String>,
+    pub workspaces: HashSet<String>,
+
+    /// Declared specs that are not semver ranges (git, file, tags...)
+    pub specs: HashSet<String>,
+
+    /// Names the dependency is installed as through `npm:` aliases
+    pub aliases: HashSet<String>,
+
+    pub usages: Vec<Usage>,
+}
+
+impl Dependency {
+    pub fn len(&self) -> usize {
+        self.workspaces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.workspaces.is_empty()
+    }
+}
+
+impl Hash for Dependency {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut versions: Vec<_> = self.versions.iter().collect();
+        versions.sort();
+
+        let mut workspaces: Vec<_> = self.workspaces.iter().collect();
+        workspaces.sort();
+
+        let mut specs: Vec<_> = self.specs.iter().collect();
+        specs.sort();
+
+        // hash the sorted versions and workspaces using `DefaultHasher`
+        let mut v_hasher = DefaultHasher::new();
+        versions.hash(&mut v_hasher);
+        let v_hash = v_hasher.finish();
+
+        let mut w_hasher = DefaultHasher::new();
+        workspaces.hash(&mut w_hasher);
+        let w_hash = w_hasher.finish();
+
+        let mut s_hasher = DefaultHasher::new();
+        specs.hash(&mut s_hasher);
+        let s_hash = s_hasher.finish();
+
+        v_hash.hash(state);
+        w_hash.hash(state);
+        s_hash.hash(state);
+    }
+}
+
+impl PartialEq for Dependency {
+    fn eq(&self, other: &Self) -> bool {
+        let mut h1 = DefaultHasher::new();
+        self.hash(&mut h1);
+
+        let mut h2 = DefaultHasher::new();
+        other.hash(&mut h2);
+
+        h1.finish() == h2.finish()
+    }
+}
+
+impl Eq for Dependency {}
+
+pub type DependencyMap = HashMap<String, Dependency>;
+
+/// What the analysis looks at, the command line flags map to these
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Sections whose dependencies are grouped
+    pub sections: Vec<Section>,
+
+    /// Ignored package names
+    pub ignore: Vec<String>,
+
+    /// Ignored workspace names
+    pub ignore_workspace: Vec<String>,
+
+    /// Only analyze the workspaces whose name matches
+    pub match_workspace: Option<Regex>,
+
+    /// Only analyze the packages whose name matches
+    pub matches: Option<Regex>,
+
+    /// Include `workspace:` dependencies, and the root manifest in `search`
+    pub include_root: bool,
+
+    /// Include the dependencies of the root manifest
+    pub check_workspace: bool,
+
+    /// Group the search results by version
+    pub deep: bool,
+
+    /// Minimum number of workspaces for a dependency to be a duplicate
+    pub min: usize,
+
+    /// Only analyze the dependencies declared by workspaces whose manifest
+    /// changed since this git ref
+    pub since: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            sections: vec![Section::Dependencies],
+            ignore: Vec::new(),
+            ignore_workspace: Vec::new(),
+            match_workspace: None,
+            matches: None,
+            include_root: false,
+            check_workspace: false,
+            deep: false,
+            min: 2,
+            since: None,
+        }
+    }
+}
+
+impl Options {
+    /// Whether the name of a package passes `ignore` and `matches`
+    pub fn accepts(&self, package_name: &str) -> bool {
+        !self.ignore.iter().any(|i| i == package_name)
+            && self
+                .matches
+                .as_ref()
+                .map(|m| m.is_match(package_name))
+                .unwrap_or(true)
+    }
+
+    /// Whether the name of a workspace passes `ignore_workspace` and
+    /// `match_workspace`
+    pub fn accepts_workspace(&self, workspace: &str) -> bool {
+        !self.ignore_workspace.iter().any(|i| i == workspace)
+            && self
+                .match_workspace
+                .as_ref()
+                .map(|m| m.is_match(workspace))
+                .unwrap_or(true)
+    }
+}
+
+pub fn add_to_dependency_map(
+    map: &mut DependencyMap,
+    package_name: &String,
+    package_version: &str,
+    manifest: &PackageJson,
+    section: Section,
+    resolver: &Resolver,
+    options: &Options,
+) {
+    let workspace = &manifest.name;
+    let declared = package_version;
+    let package_version = match resolver.resolve(workspace, package_name, package_version) {
+        Some(v) => v,
+        None => return,
+    };
+
+    let spec = Spec::parse(&package_version);
+    let version = spec.version();
+
+    if spec.kind() == SpecKind::Workspace && !options.include_root {
+        return;
+    }
+
+    let declared_name = package_name;
+
+    // aliased dependencies are grouped under the real package name
+    let (package_name, alias, spec) = match spec.dealias() {
+        Some((name, inner)) => (&name.to_string(), Some(package_name), inner.clone()),
+        None => (package_name, None, spec),
+    };
+
+    // skip ignored packages and workspaces
+    if !options.accepts(package_name) || !options.accepts_workspace(workspace) {
+        return;
+    }
+
+    let dependency = map.entry(package_name.to_owned()).or_default();
+
+    dependency.workspaces.insert(workspace.to_owned());
+
+    if let Some(alias) = alias {
+        dependency.aliases.insert(alias.to_owned());
+    }
+
+    dependency.usages.push(Usage {
+        workspace: workspace.to_owned(),
+        section,
+        spec: declared.to_owned(),
+        version: version.clone(),
+        alias: alias.cloned(),
+        location: manifest.locate(section, declared_name),
+    });
+
+    match version {
+        Some(version) => dependency.versions.insert(version),
+        None => dependency.specs.insert(spec.to_string()),
+    };
+}
+
+/// What `Options::since` limits the analysis to
+#[derive(Debug, Clone)]
+pub struct Touched {
+    pub dependencies: BTreeSet<String>,
+}
+
+impl Touched {
+    /// Dependencies declared by the workspaces whose manifest changed since
+    /// `since`, `None` when the whole monorepo is analyzed
+    pub fn since(monorepo: &Monorepo, since: Option<&str>) -> Result<Option<Touched>> {
+        let since = match since {
+            Some(since) => since,
+            None => return Ok(None),
+        };
+
+        let changed: BTreeSet<_> = git::changed_files(&monorepo.root, since)?
+            .iter()
+            .map(|file| normalize_path(Path::new(file)))
+            .collect();
+
+        let mut touched = Touched {
+            dependencies: BTreeSet::new(),
+        };
+
+        for manifest in monorepo.manifests(true) {
+            if !changed.contains(&normalize_path(monorepo.relative(&manifest.path))) {
+                continue;
+            }
+
+            for section in [
+                Section::Dependencies,
+                Section::DevDependencies,
+                Section::PeerDependencies,
+            ] {
+                for (name, spec) in manifest.section(section).0.iter() {
+                    // aliased dependencies are reported under the real package name
+                    if let Some((real, _)) = Spec::parse(spec).dealias() {
+                        touched.dependencies.insert(real.to_string());
+                    }
+
+                    touched.dependencies.insert(name.clone());
+                }
+            }
+        }
+
+        Ok(Some(touched))
+    }
+
+    pub fn contains(touched: &Option<Touched>, dependency: &str) -> bool {
+        touched
+            .as_ref()
+            .map(|t| t.dependencies.contains(dependency))
+            .unwrap_or(true)
+    }
+}
+
+/// What `search` is looking for
+#[derive(Debug, Clone)]
+pub struct SearchFilter {
+    pub value: Regex,
+    pub range: Option<VersionRange>,
+
+    /// Searched sections, `Options::sections` when empty
+    pub sections: Vec<Section>,
+
+    /// Accepted spec kinds, any when empty
+    pub kinds: Vec<SpecKind>,
+}
+
+impl SearchFilter {
+    /// Declared and resolved specs must match the kind and range filters
+    pub fn matches(&self, declared: &Spec, resolved: &Spec) -> bool {
+        let kind = self.kinds.is_empty()
+            || self.kinds.contains(&declared.kind())
+            || self.kinds.contains(&resolved.kind());
+
+        // the lowest version allowed by the spec is the one that may get installed
+        let range = match &self.range {
+            None => true,
+            Some(range) => resolved
+                .range()
+                .and_then(|r| r.min_version())
+                .map(|v| range.satisfies(&v))
+                .unwrap_or(false),
+        };
+
+        kind && range
+    }
+}
+
+pub fn search_deps(
+    manifest: &PackageJson,
+    map: &mut DependencyMap,
+    resolver: &Resolver,
+    options: &Options,
+    filter: &SearchFilter,
+    touched: &Option<Touched>,
+) {
+    let sections = match filter.sections.is_empty() {
+        true => &options.sections,
+        false => &filter.sections,
+    };
+
+    for section in sections.iter().copied() {
+        for (name, declared) in manifest.section(section).0.iter() {
+            let version = match resolver.resolve(&manifest.name, name, declared) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let spec = Spec::parse(&version);
+
+            // aliases are found and grouped under the real package name
+            let (real, alias) = match spec.dealias() {
+                Some((real, _)) => (real.to_string(), Some(name.clone())),
+                None => (name.clone(), None),
+            };
+
+            // search dependency
+            if !filter.value.is_match(&real) && !filter.value.is_match(name) {
+                continue;
+            }
+
+            if !Touched::contains(touched, &real) {
+                continue;
+            }
+
+            if !filter.matches(&Spec::parse(declared), &spec) {
+                continue;
+            }
+
+            let normalized = spec.version();
+            let version = normalized.clone().unwrap_or_else(|| spec.to_string());
+
+            let pkg_name = if options.deep {
+                format!("{}@{}", real, version)
+            } else {
+                real
+            };
+
+            let dependency = map.entry(pkg_name).or_default();
+
+            dependency.workspaces.insert(manifest.name.clone());
+            dependency.usages.push(Usage {
+                workspace: manifest.name.clone(),
+                section,
+                spec: declared.clone(),
+                version: normalized,
+                alias,
+                location: manifest.locate(section, name),
+            });
+        }
+    }
+}
+
+fn sort_usages(usages: &mut [Usage]) {
+    usages.sort_by(|a, b| {
+        (&a.workspace, a.section, &a.spec).cmp(&(&b.workspace, b.section, &b.spec))
+    });
+}
+
+/// Result of the default check
+#[derive(Debug)]
+pub struct Analysis {
+    /// Dependencies declared by the analyzed workspaces, the report keeps the
+    /// ones declared by at least `Options::min` of them
+    pub duplicates: Duplicates,
+
+    /// Set when `Options::since` limited the analysis
+    pub touched: Option<Touched>,
+}
+
+impl Analysis {
+    /// Groups the dependencies declared by the workspaces of the monorepo.
+    ///
+    /// Entries are sorted by name, their workspaces and versions too, and
+    /// their usages by workspace, section and spec.
+    pub fn run(monorepo: &Monorepo, options: &Options) -> Result<Analysis> {
+        let resolver = &monorepo.resolver;
+        let mut dependency_map: DependencyMap = DependencyMap::new();
+
+        for pkg in monorepo.manifests(options.check_workspace) {
+            for section in options.sections.iter().copied() {
+                for (pkg_name, version) in pkg.section(section).0.iter() {
+                    add_to_dependency_map(
+                        &mut dependency_map,
+                        pkg_name,
+                        version,
+                        pkg,
+                        section,
+                        resolver,
+                        options,
+                    );
+                }
+            }
+        }
+
+        let mut entries: Vec<DuplicateEntry> = Vec::new();
+
+        for (name, packages) in dependency_map {
+            let count = packages.len();
+
+            let mut workspaces = packages
+                .clone()
+                .workspaces
+                .into_iter()
+                .collect::<Vec<String>>();
+
+            workspaces.sort();
+
+            // versions may be ranges without a lower bound (`*`), which are
+            // sorted but kept as declared
+            let mut versions = packages.versions.iter().cloned().collect::<Vec<String>>();
+            versions.sort_by_cached_key(|v| (SemanticVersion::from_str(v).ok(), v.clone()));
+
+            let mut specs = packages.specs.into_iter().collect::<Vec<String>>();
+            specs.sort();
+
+            let mut aliases = packages.aliases.into_iter().collect::<Vec<String>>();
+            aliases.sort();
+
+            let mut usages = packages.usages;
+            sort_usages(&mut usages);
+
+            entries.push(DuplicateEntry {
+                name,
+                count,
+                workspaces,
+                versions,
+                specs,
+                aliases,
+                usages,
+            });
+        }
+
+        let touched = Touched::since(monorepo, options.since.as_deref())?;
+        entries.retain(|e| Touched::contains(&touched, &e.name));
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Analysis {
+            duplicates: Duplicates {
+                entries,
+                min: options.min,
+                deep: options.deep,
+            },
+            touched,
+        })
+    }
+
+    /// Workspaces declaring the dependencies matching the filter.
+    ///
+    /// Matches are sorted by name, their workspaces too, and their usages by
+    /// workspace, section and spec.
+    pub fn search(monorepo: &Monorepo, options: &Options, filter: &SearchFilter) -> Result<Search> {
+        let touched = Touched::since(monorepo, options.since.as_deref())?;
+        let mut dependencies = DependencyMap::new();
+
+        for manifest in monorepo.manifests(options.include_root) {
+            search_deps(
+                manifest,
+                &mut dependencies,
+                &monorepo.resolver,
+                options,
+                filter,
+                &touched,
+            );
+        }
+
+        let mut matches: Vec<SearchMatch> = dependencies
+            .into_iter()
+            .map(|(name, dependency)| {
+                let mut workspaces = dependency.workspaces.into_iter().collect::<Vec<_>>();
+                workspaces.sort();
+
+                let mut usages = dependency.usages;
+                sort_usages(&mut usages);
+
+                SearchMatch {
+                    name,
+                    workspaces,
+                    usages,
+                }
+            })
+            .collect();
+
+        matches.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Search(matches))
+    }
+}
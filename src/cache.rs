@@ -0,0 +1,225 @@
+//! On-disk cache of parsed files, an entry is reused as long as the file it
+//! was parsed from keeps the same size, modification time and content hash.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Default location of the cache, relative to the root of the monorepo
+pub const DEFAULT_DIR: &str = "node_modules/.cache/monocheck";
+
+/// Identifies the content of a file without reading it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stamp {
+    pub size: u64,
+    pub secs: u64,
+    pub nanos: u32,
+}
+
+impl Stamp {
+    pub fn of(path: &Path) -> Option<Stamp> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+        Some(Stamp {
+            size: metadata.len(),
+            secs: modified.as_secs(),
+            nanos: modified.subsec_nanos(),
+        })
+    }
+}
+
+/// FNV-1a hash of the content of a file, which catches the edits that keep
+/// the size and modification time (coarse mtime, restored timestamps...)
+fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Identifies a version of a file by its stamp and the hash of its content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Key {
+    stamp: Stamp,
+    hash: u64,
+}
+
+impl Key {
+    /// Reads and hashes the file
+    pub fn of(path: &Path) -> Option<Key> {
+        let stamp = Stamp::of(path)?;
+        let bytes = fs::read(path).ok()?;
+
+        Some(Key {
+            stamp,
+            hash: content_hash(&bytes),
+        })
+    }
+}
+
+/// Outcome of [`Cache::get`]
+#[derive(Debug)]
+pub enum Lookup<'a, T> {
+    /// The file did not change since the value was cached
+    Hit(&'a T),
+    /// The file changed or is not cached: the key to insert the new value
+    /// with, `None` when the file cannot be read
+    Miss(Option<Key>),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry<T> {
+    key: Key,
+    value: T,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Store<T> {
+    /// Entries written by another version of the crate are discarded
+    version: String,
+    entries: BTreeMap<PathBuf, Entry<T>>,
+}
+
+/// Parsed values of a kind of file (manifests, lockfiles...) keyed by path
+#[derive(Debug)]
+pub struct Cache<T> {
+    path: PathBuf,
+    entries: BTreeMap<PathBuf, Entry<T>>,
+    dirty: bool,
+}
+
+impl<T: Serialize + DeserializeOwned> Cache<T> {
+    /// Reads `<dir>/<name>.json`, a missing or invalid cache is empty
+    pub fn open(dir: &Path, name: &str) -> Self {
+        let path = dir.join(format!("{}.json", name));
+
+        let entries = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Store<T>>(&bytes).ok())
+            .filter(|store| store.version == env!("CARGO_PKG_VERSION"))
+            .map(|store| store.entries)
+            .unwrap_or_default();
+
+        Self {
+            path,
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// The value parsed from `path` if the file did not change since, the
+    /// key to insert the new value with otherwise. The file is read and
+    /// hashed once per lookup.
+    pub fn get(&self, path: &Path) -> Lookup<'_, T> {
+        let key = Key::of(path);
+
+        match self.entries.get(path) {
+            Some(entry) if Some(entry.key) == key => Lookup::Hit(&entry.value),
+            _ => Lookup::Miss(key),
+        }
+    }
+
+    /// Caches a value parsed from `path`, `key` being the one returned by
+    /// the lookup that missed
+    pub fn insert(&mut self, path: &Path, key: Key, value: T) {
+        self.entries
+            .insert(path.to_path_buf(), Entry { key, value });
+        self.dirty = true;
+    }
+
+    /// Writes the cache if it changed, dropping the entries of deleted files
+    pub fn save(&mut self) -> io::Result<()> {
+        let before = self.entries.len();
+        self.entries.retain(|path, _| path.exists());
+
+        if !self.dirty && before == self.entries.len() {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let store = Store {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            entries: std::mem::take(&mut self.entries),
+        };
+
+        let result = serde_json::to_vec(&store)
+            .map_err(io::Error::from)
+            .and_then(|bytes| fs::write(&self.path, bytes));
+
+        self.entries = store.entries;
+        self.dirty = false;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+
+    /// A directory of its own for a test, removed even when the test fails
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos();
+            let dir = std::env::temp_dir().join(format!(
+                "monocheck-{}-{}-{}",
+                name,
+                std::process::id(),
+                nanos
+            ));
+
+            fs::create_dir(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn edits_keeping_size_and_mtime_invalidate_the_entry() {
+        let temp = TempDir::new("cache");
+        let dir = &temp.0;
+        let path = dir.join("package.json");
+
+        fs::write(&path, r#"{"name":"a"}"#).unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+
+        let mut cache: Cache<String> = Cache::open(dir, "test");
+        let key = match cache.get(&path) {
+            Lookup::Miss(Some(key)) => key,
+            lookup => panic!("unexpected {:?}", lookup),
+        };
+        cache.insert(&path, key, "a".to_string());
+        assert!(matches!(cache.get(&path), Lookup::Hit(value) if value == "a"));
+
+        fs::write(&path, r#"{"name":"b"}"#).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        assert_eq!(Stamp::of(&path), Some(key.stamp));
+        assert!(matches!(cache.get(&path), Lookup::Miss(Some(k)) if k != key));
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt::Write;
+
+use crate::report::Usage;
+
+pub const HEADER: [&str; 6] = [
+    "dependency",
+    "workspace",
+    "section",
+    "spec",
+    "version",
+    "alias",
+];
+
+/// Quotes a CSV field when needed
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+/// TSV has no quoting, separators are replaced by spaces
+fn tsv_field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Renders a header and rows as CSV (or TSV)
+pub fn table(header: &[&str], rows: &[Vec<String>], tsv: bool) -> String {
+    let (separator, field): (&str, fn(&str) -> String) = match tsv {
+        true => ("\t", tsv_field),
+        false => (",", csv_field),
+    };
+
+    let mut out = String::new();
+    let header = header.iter().map(|h| field(h)).collect::<Vec<_>>();
+    let _ = writeln!(out, "{}", header.join(separator));
+
+    for row in rows {
+        let values = row.iter().map(|v| field(v)).collect::<Vec<_>>();
+        let _ = writeln!(out, "{}", values.join(separator));
+    }
+
+    out
+}
+
+/// Flat export with one row per (dependency, workspace, section, spec, version, alias)
+pub fn usages<'a>(rows: impl IntoIterator<Item = (&'a str, &'a Usage)>, tsv: bool) -> String {
+    let rows = rows
+        .into_iter()
+        .map(|(dependency, usage)| {
+            vec![
+                dependency.to_string(),
+                usage.workspace.clone(),
+                usage.section.to_string(),
+                usage.spec.clone(),
+                usage.version.clone().unwrap_or_default(),
+                usage.alias.clone().unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    table(&HEADER, &rows, tsv)
+}
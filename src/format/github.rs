@@ -0,0 +1,110 @@
+use std::fmt::Write;
+
+use super::{Finding, Level};
+use crate::models::source_map::Location;
+use crate::report::DuplicateEntry;
+
+/// Escapes the message of a workflow command
+pub fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a property value of a workflow command
+pub fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Writes a single workflow command, eg: `::warning file=a,line=1::message`
+fn command(out: &mut String, name: &str, location: Option<&Location>, title: &str, message: &str) {
+    let mut properties = Vec::new();
+
+    if let Some(location) = location {
+        properties.push(format!(
+            "file={}",
+            escape_property(location.path.trim_start_matches("./"))
+        ));
+        properties.push(format!("line={}", location.line));
+        properties.push(format!("col={}", location.column));
+    }
+
+    properties.push(format!("title={}", escape_property(title)));
+
+    let _ = writeln!(
+        out,
+        "::{} {}::{}",
+        name,
+        properties.join(","),
+        escape_data(message)
+    );
+}
+
+/// Workflow command of a level, notes become `::notice`
+fn command_name(level: Level) -> &'static str {
+    match level {
+        Level::Note => "notice",
+        Level::Warning => "warning",
+        Level::Error => "error",
+    }
+}
+
+/// Renders generic findings as workflow commands (notes become `::notice`)
+pub fn findings(findings: &[Finding]) -> String {
+    let mut out = String::new();
+
+    for finding in findings {
+        command(
+            &mut out,
+            command_name(finding.level),
+            finding.location.as_ref(),
+            &finding.title,
+            &finding.message,
+        );
+    }
+
+    out
+}
+
+/// Renders GitHub Actions workflow commands: an `::error` for every declaration
+/// of a dependency with conflicting versions and a `::warning` for every
+/// declaration of a duplicated one.
+pub fn duplicates(entries: &[DuplicateEntry]) -> String {
+    let mut out = String::new();
+
+    for entry in entries {
+        let conflict = entry.is_conflict();
+        let level = command_name(entry.level());
+        let title = match conflict {
+            true => "Version conflict",
+            false => "Duplicate dependency",
+        };
+
+        for usage in entry.usages.iter() {
+            let others = entry
+                .usages
+                .iter()
+                .filter(|u| u.workspace != usage.workspace)
+                .map(|u| format!("{} ({})", u.workspace, u.spec))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let message = match conflict {
+                true => format!(
+                    "{}@{} in {} conflicts with {}",
+                    entry.name, usage.spec, usage.workspace, others
+                ),
+                false => format!(
+                    "{} in {} is also declared by {}",
+                    entry.name, usage.workspace, others
+                ),
+            };
+
+            let title = format!("{}: {}", title, entry.name);
+            command(&mut out, level, usage.location.as_ref(), &title, &message);
+        }
+    }
+
+    out
+}
@@ -0,0 +1,78 @@
+use std::fmt::Write;
+
+use serde_json::json;
+
+use crate::report::DuplicateEntry;
+
+const TEMPLATE: &str = include_str!("report.html");
+
+const STYLE: &str = "body { font: 14px/1.5 sans-serif; margin: 24px; } \
+    table { border-collapse: collapse; } \
+    th, td { border: 1px solid #d0d7de; padding: 6px 10px; text-align: left; vertical-align: top; } \
+    th { background: #f6f8fa; }";
+
+/// Renders the duplicates report as a self-contained HTML page.
+///
+/// The entries are embedded as JSON and rendered client side, so the file
+/// can be opened or attached to a CI run without any external asset.
+pub fn duplicates(entries: &[DuplicateEntry], min: usize) -> String {
+    let data = json!({ "min": min, "entries": entries }).to_string();
+
+    // keep the payload from closing the surrounding <script> tag
+    let data = data.replace("</", "<\\/");
+
+    TEMPLATE.replacen("/*REPORT*/", &data, 1)
+}
+
+/// Escapes text for HTML content and attributes
+pub fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders rows as a minimal static page with a single table
+pub fn table(title: &str, header: &[&str], rows: &[Vec<String>], summary: &str) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "<!DOCTYPE html>");
+    let _ = writeln!(out, r#"<html lang="en">"#);
+    let _ = writeln!(out, "<head>");
+    let _ = writeln!(out, r#"<meta charset="utf-8">"#);
+    let _ = writeln!(out, "<title>monocheck {}</title>", escape(title));
+    let _ = writeln!(out, "<style>{}</style>", STYLE);
+    let _ = writeln!(out, "</head>");
+    let _ = writeln!(out, "<body>");
+    let _ = writeln!(out, "<h1>monocheck {}</h1>", escape(title));
+    let _ = writeln!(out, "<p>{}</p>", escape(summary));
+
+    if !rows.is_empty() {
+        let _ = writeln!(out, "<table>");
+        let _ = writeln!(out, "<thead><tr>");
+
+        for column in header {
+            let _ = writeln!(out, "<th>{}</th>", escape(column));
+        }
+
+        let _ = writeln!(out, "</tr></thead>");
+        let _ = writeln!(out, "<tbody>");
+
+        for row in rows {
+            let cells = row
+                .iter()
+                .map(|c| format!("<td>{}</td>", escape(c).replace('\n', "<br>")))
+                .collect::<String>();
+
+            let _ = writeln!(out, "<tr>{}</tr>", cells);
+        }
+
+        let _ = writeln!(out, "</tbody>");
+        let _ = writeln!(out, "</table>");
+    }
+
+    let _ = writeln!(out, "</body>");
+    let _ = writeln!(out, "</html>");
+    out
+}
@@ -0,0 +1,174 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::Finding;
+use crate::models::package_json::Section;
+use crate::report::{DuplicateEntry, Usage};
+
+/// Escapes text for XML attributes and content
+pub fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+struct Case<'a> {
+    name: &'a str,
+    usages: Vec<&'a Usage>,
+    failure: Option<String>,
+}
+
+/// Renders every checked dependency as a JUnit test case, one suite per
+/// dependency section. A case fails when the dependency is declared by at
+/// least `min` workspaces or with different versions.
+pub fn duplicates(entries: &[DuplicateEntry], min: usize) -> String {
+    let mut suites: BTreeMap<Section, Vec<Case>> = BTreeMap::new();
+
+    for entry in entries {
+        let mut by_section: BTreeMap<Section, Vec<&Usage>> = BTreeMap::new();
+
+        for usage in entry.usages.iter() {
+            by_section.entry(usage.section).or_default().push(usage);
+        }
+
+        for (section, usages) in by_section {
+            let workspaces: BTreeSet<&String> = usages.iter().map(|u| &u.workspace).collect();
+            let versions: BTreeSet<&String> = usages
+                .iter()
+                .map(|u| u.version.as_ref().unwrap_or(&u.spec))
+                .collect();
+
+            let failure = if versions.len() > 1 {
+                Some(format!(
+                    "{} versions declared by {} workspaces",
+                    versions.len(),
+                    workspaces.len()
+                ))
+            } else if workspaces.len() >= min {
+                Some(format!(
+                    "declared by {} workspaces (min: {})",
+                    workspaces.len(),
+                    min
+                ))
+            } else {
+                None
+            };
+
+            suites.entry(section).or_default().push(Case {
+                name: &entry.name,
+                usages,
+                failure,
+            });
+        }
+    }
+
+    let tests: usize = suites.values().map(|c| c.len()).sum();
+    let failures: usize = suites
+        .values()
+        .flatten()
+        .filter(|c| c.failure.is_some())
+        .count();
+
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        out,
+        r#"<testsuites name="monocheck" tests="{}" failures="{}">"#,
+        tests, failures
+    );
+
+    for (section, cases) in suites.iter() {
+        let failures = cases.iter().filter(|c| c.failure.is_some()).count();
+
+        let _ = writeln!(
+            out,
+            r#"  <testsuite name="{}" tests="{}" failures="{}">"#,
+            section,
+            cases.len(),
+            failures
+        );
+
+        for case in cases {
+            let _ = write!(
+                out,
+                r#"    <testcase name="{}" classname="{}""#,
+                escape(case.name),
+                section
+            );
+
+            match &case.failure {
+                None => {
+                    let _ = writeln!(out, " />");
+                }
+                Some(message) => {
+                    let details = case
+                        .usages
+                        .iter()
+                        .map(|u| format!("{}: {}", u.workspace, u.spec))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+
+                    let _ = writeln!(out, ">");
+                    let _ = writeln!(
+                        out,
+                        r#"      <failure message="{}" type="duplicate">{}</failure>"#,
+                        escape(message),
+                        escape(&details)
+                    );
+                    let _ = writeln!(out, "    </testcase>");
+                }
+            }
+        }
+
+        let _ = writeln!(out, "  </testsuite>");
+    }
+
+    let _ = writeln!(out, "</testsuites>");
+    out
+}
+
+/// Renders generic findings as a single failing test suite, one test case per
+/// finding. A report without findings yields an empty, passing suite.
+pub fn findings(name: &str, findings: &[Finding]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        out,
+        r#"<testsuites name="monocheck" tests="{0}" failures="{0}">"#,
+        findings.len()
+    );
+    let _ = writeln!(
+        out,
+        r#"  <testsuite name="{0}" tests="{1}" failures="{1}">"#,
+        escape(name),
+        findings.len()
+    );
+
+    for finding in findings {
+        let classname = match &finding.location {
+            Some(location) => location.path.clone(),
+            None => name.to_string(),
+        };
+
+        let _ = writeln!(
+            out,
+            r#"    <testcase name="{}" classname="{}">"#,
+            escape(&finding.title),
+            escape(&classname)
+        );
+        let _ = writeln!(
+            out,
+            r#"      <failure message="{}" type="{}" />"#,
+            escape(&finding.message),
+            finding.rule
+        );
+        let _ = writeln!(out, "    </testcase>");
+    }
+
+    let _ = writeln!(out, "  </testsuite>");
+    let _ = writeln!(out, "</testsuites>");
+    out
+}
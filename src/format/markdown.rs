@@ -0,0 +1,117 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::report::DuplicateEntry;
+
+/// Escapes characters that would break a table cell
+pub fn escape(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+fn code_list<'a>(values: impl IntoIterator<Item = &'a String>) -> String {
+    values
+        .into_iter()
+        .map(|v| format!("`{}`", escape(v)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Renders rows as a markdown table followed by a summary line
+pub fn table(header: &[&str], rows: &[Vec<String>], summary: &str) -> String {
+    let mut out = String::new();
+
+    if !rows.is_empty() {
+        let _ = writeln!(out, "| {} |", header.join(" | "));
+        let _ = writeln!(out, "|{}", " --- |".repeat(header.len()));
+
+        for row in rows {
+            let cells = row.iter().map(|c| escape(c)).collect::<Vec<_>>();
+            let _ = writeln!(out, "| {} |", cells.join(" | "));
+        }
+
+        let _ = writeln!(out);
+    }
+
+    let _ = writeln!(out, "**{}**", escape(summary));
+    out
+}
+
+/// Renders the duplicates report as GitHub/GitLab flavored markdown
+pub fn duplicates(entries: &[DuplicateEntry], min: usize) -> String {
+    let mut out = String::new();
+
+    if entries.is_empty() {
+        let _ = writeln!(out, "No duplicate dependencies found (min: {})", min);
+        return out;
+    }
+
+    let _ = writeln!(out, "| Dependency | Count | Versions | Workspaces |");
+    let _ = writeln!(out, "| --- | ---: | --- | --- |");
+
+    for entry in entries {
+        let mut versions = code_list(entry.versions.iter().chain(entry.specs.iter()));
+        if versions.is_empty() {
+            versions = "-".to_string();
+        }
+
+        let _ = writeln!(
+            out,
+            "| `{}` | {} | {} | {} |",
+            escape(&entry.name),
+            entry.count,
+            versions,
+            escape(&entry.workspaces.join(", "))
+        );
+    }
+
+    let _ = writeln!(out);
+
+    for entry in entries {
+        let _ = writeln!(out, "<details>");
+        let _ = writeln!(
+            out,
+            "<summary><code>{}</code> — {} workspaces, {} versions</summary>",
+            entry.name,
+            entry.count,
+            entry.versions.len() + entry.specs.len()
+        );
+        let _ = writeln!(out);
+
+        if !entry.aliases.is_empty() {
+            let _ = writeln!(out, "Installed as {}", code_list(entry.aliases.iter()));
+            let _ = writeln!(out);
+        }
+
+        let _ = writeln!(out, "| Workspace | Section | Spec | Version |");
+        let _ = writeln!(out, "| --- | --- | --- | --- |");
+
+        for usage in entry.usages.iter() {
+            let _ = writeln!(
+                out,
+                "| {} | {} | `{}` | {} |",
+                escape(&usage.workspace),
+                usage.section,
+                escape(&usage.spec),
+                usage.version.as_deref().map(escape).unwrap_or("-".into())
+            );
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "</details>");
+        let _ = writeln!(out);
+    }
+
+    let workspaces: BTreeSet<&String> = entries.iter().flat_map(|e| e.workspaces.iter()).collect();
+    let conflicts = entries.iter().filter(|e| e.is_conflict()).count();
+
+    let _ = writeln!(
+        out,
+        "**{} duplicated dependencies** across {} workspaces, {} with conflicting versions (min: {})",
+        entries.len(),
+        workspaces.len(),
+        conflicts,
+        min
+    );
+
+    out
+}
@@ -0,0 +1,208 @@
+pub mod csv;
+pub mod github;
+pub mod html;
+pub mod junit;
+pub mod markdown;
+pub mod sarif;
+
+use colored::{Color, Colorize};
+use colored_json::ToColoredJson;
+use prettytable::{Cell, Row, Table};
+use serde::Serialize;
+
+use crate::models::source_map::Location;
+use crate::{Format, Sort};
+
+#[derive(thiserror::Error, Debug)]
+pub enum RenderError {
+    #[error("cannot serialize to json: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("cannot serialize to yaml: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+pub type Result<T> = std::result::Result<T, RenderError>;
+
+/// Severity of a finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Note => "note",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// A problem reported by the annotation formats (SARIF, JUnit, GitHub)
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// Rule identifier, eg: `peer-drift`
+    pub rule: &'static str,
+    pub level: Level,
+
+    /// Short title, usually the package the finding is about
+    pub title: String,
+    pub message: String,
+
+    pub location: Option<Location>,
+}
+
+/// A row of a report that can be reordered with `--sort`
+pub trait Sortable {
+    fn name(&self) -> &str;
+
+    /// Number of workspaces involved
+    fn count(&self) -> usize;
+
+    /// Number of distinct versions (or specs) involved
+    fn versions(&self) -> usize;
+
+    /// `None` when the row does not describe a problem
+    fn severity(&self) -> Option<Level>;
+
+    /// Installed size in bytes, when known
+    fn size(&self) -> u64 {
+        0
+    }
+}
+
+/// Sorts rows by name or by the given metric (largest first), falling back to
+/// the name so that the order never depends on how the rows were collected.
+pub fn sort<T: Sortable>(items: &mut [T], sort: Sort, reverse: bool) {
+    items.sort_by(|a, b| {
+        let order = match sort {
+            Sort::Name => std::cmp::Ordering::Equal,
+            Sort::Count => b.count().cmp(&a.count()),
+            Sort::Versions => b.versions().cmp(&a.versions()),
+            Sort::Severity => b.severity().cmp(&a.severity()),
+            Sort::Size => b.size().cmp(&a.size()),
+        };
+
+        // the largest first among rows of the same count (`transitive`)
+        let order = match sort {
+            Sort::Count => order.then_with(|| b.size().cmp(&a.size())),
+            _ => order,
+        };
+
+        order.then_with(|| a.name().cmp(b.name()))
+    });
+
+    if reverse {
+        items.reverse();
+    }
+}
+
+/// A report that can be printed in every output format.
+///
+/// Reports describe themselves as a table (`header` and `rows`) and as a list
+/// of findings; the per-format methods build on those and can be overridden
+/// when a report has a richer representation.
+pub trait Render: Serialize {
+    /// Name of the report, used by the JUnit and HTML outputs
+    fn title(&self) -> &'static str;
+
+    fn header(&self) -> Vec<&'static str>;
+
+    fn rows(&self) -> Vec<Vec<String>>;
+
+    /// Line printed after the table, or instead of it when there are no rows
+    fn summary(&self) -> String;
+
+    /// Reorders the rows of the report, see [`sort`]
+    fn sort(&mut self, sort: Sort, reverse: bool);
+
+    /// Order of the rows when none is requested
+    fn default_sort(&self) -> Sort {
+        Sort::Name
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        Vec::new()
+    }
+
+    /// Color of a table cell, only used for the terminal output
+    fn color(&self, _row: usize, _column: usize) -> Option<Color> {
+        None
+    }
+
+    fn table(&self, color: bool) -> String {
+        let rows = self.rows();
+
+        if rows.is_empty() {
+            return format!("{}\n", self.summary());
+        }
+
+        let mut table = Table::new();
+        table.add_row(Row::new(self.header().into_iter().map(Cell::new).collect()));
+
+        for (i, row) in rows.iter().enumerate() {
+            let cells = row
+                .iter()
+                .enumerate()
+                .map(|(j, value)| match self.color(i, j) {
+                    Some(c) if color => Cell::new(&value.color(c).to_string()),
+                    _ => Cell::new(value),
+                })
+                .collect();
+
+            table.add_row(Row::new(cells));
+        }
+
+        format!("{}{}\n", table, self.summary())
+    }
+
+    fn markdown(&self) -> String {
+        markdown::table(&self.header(), &self.rows(), &self.summary())
+    }
+
+    fn csv(&self, tsv: bool) -> String {
+        csv::table(&self.header(), &self.rows(), tsv)
+    }
+
+    fn sarif(&self) -> serde_json::Value {
+        sarif::findings(&self.findings())
+    }
+
+    fn junit(&self) -> String {
+        junit::findings(self.title(), &self.findings())
+    }
+
+    fn github(&self) -> String {
+        github::findings(&self.findings())
+    }
+
+    fn html(&self) -> String {
+        html::table(self.title(), &self.header(), &self.rows(), &self.summary())
+    }
+}
+
+/// Renders a report in the given format
+pub fn render<R: Render>(report: &R, format: Format, color: bool) -> Result<String> {
+    let output = match format {
+        Format::Table => report.table(color),
+        Format::Json if color => {
+            serde_json::to_string_pretty(report)?.to_colored_json_auto()? + "\n"
+        }
+        Format::Json => serde_json::to_string_pretty(report)? + "\n",
+        Format::Yaml => serde_yaml::to_string(report)?,
+        Format::Markdown => report.markdown(),
+        Format::Sarif => serde_json::to_string_pretty(&report.sarif())? + "\n",
+        Format::Junit => report.junit(),
+        Format::Github => report.github(),
+        Format::Html => report.html(),
+        Format::Csv => report.csv(false),
+        Format::Tsv => report.csv(true),
+    };
+
+    Ok(output)
+}
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+use super::{Finding, Level};
+use crate::models::source_map::Location;
+use crate::report::DuplicateEntry;
+
+pub const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+pub const DUPLICATE_RULE: &str = "duplicate-dependency";
+pub const CONFLICT_RULE: &str = "version-conflict";
+
+fn rules() -> Value {
+    json!([
+        {
+            "id": DUPLICATE_RULE,
+            "name": "DuplicateDependency",
+            "shortDescription": { "text": "Dependency declared by multiple workspaces" },
+            "defaultConfiguration": { "level": Level::Warning.as_str() }
+        },
+        {
+            "id": CONFLICT_RULE,
+            "name": "VersionConflict",
+            "shortDescription": { "text": "Workspaces declare different versions of the same dependency" },
+            "defaultConfiguration": { "level": Level::Error.as_str() }
+        }
+    ])
+}
+
+/// Artifact uri relative to the repository root
+pub fn uri(path: &str) -> String {
+    path.trim_start_matches("./").to_string()
+}
+
+fn location(location: &Location) -> Value {
+    json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": uri(&location.path) },
+            "region": {
+                "startLine": location.line,
+                "startColumn": location.column
+            }
+        }
+    })
+}
+
+fn log(rules: Value, results: Vec<Value>) -> Value {
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [
+            {
+                "tool": {
+                    "driver": {
+                        "name": "monocheck",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": "https://github.com/Rawnly/monocheck",
+                        "rules": rules
+                    }
+                },
+                "results": results
+            }
+        ]
+    })
+}
+
+/// Renders generic findings as a SARIF 2.1.0 log, with a rule per finding kind
+pub fn findings(findings: &[Finding]) -> Value {
+    let mut rules = BTreeMap::new();
+
+    for finding in findings {
+        rules.entry(finding.rule).or_insert(finding.level);
+    }
+
+    let rules = rules
+        .iter()
+        .map(|(id, level)| {
+            json!({
+                "id": id,
+                "defaultConfiguration": { "level": level.as_str() }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = findings
+        .iter()
+        .map(|f| {
+            json!({
+                "ruleId": f.rule,
+                "level": f.level.as_str(),
+                "message": { "text": f.message },
+                "locations": f.location.iter().map(location).collect::<Vec<_>>(),
+                "partialFingerprints": {
+                    "finding/v1": format!("{}:{}", f.rule, f.title)
+                }
+            })
+        })
+        .collect();
+
+    log(Value::Array(rules), results)
+}
+
+/// Renders the duplicates report as a SARIF 2.1.0 log with one result per
+/// declaration, pointing at the dependency entry of each `package.json`.
+pub fn duplicates(entries: &[DuplicateEntry]) -> Value {
+    let mut results = Vec::new();
+
+    for entry in entries {
+        let conflict = entry.is_conflict();
+        let rule = match conflict {
+            true => CONFLICT_RULE,
+            false => DUPLICATE_RULE,
+        };
+
+        for usage in entry.usages.iter() {
+            let others = entry
+                .usages
+                .iter()
+                .filter(|u| u.workspace != usage.workspace)
+                .map(|u| format!("{} ({})", u.workspace, u.spec))
+                .collect::<Vec<_>>();
+
+            let text = match conflict {
+                true => format!(
+                    "`{}@{}` conflicts with the versions declared by {}",
+                    entry.name,
+                    usage.spec,
+                    others.join(", ")
+                ),
+                false => format!("`{}` is also declared by {}", entry.name, others.join(", ")),
+            };
+
+            results.push(json!({
+                "ruleId": rule,
+                "level": entry.level().as_str(),
+                "message": { "text": text },
+                "locations": usage.location.iter().map(location).collect::<Vec<_>>(),
+                "partialFingerprints": {
+                    "dependency/v1": format!("{}:{}:{}", entry.name, usage.workspace, usage.section)
+                }
+            }));
+        }
+    }
+
+    log(rules(), results)
+}
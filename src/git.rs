@@ -0,0 +1,60 @@
+//! Reads the repository through the `git` command line, run from the root of
+//! the monorepo: paths are relative to it.
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(thiserror::Error, Debug)]
+pub enum GitError {
+    #[error("cannot run git: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("`git {0}` failed: {1}")]
+    Command(String, String),
+}
+
+pub type Result<T> = std::result::Result<T, GitError>;
+
+fn run(root: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git").current_dir(root).args(args).output()?;
+
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+        false => Err(GitError::Command(
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        )),
+    }
+}
+
+/// Content of a file at a revision, `None` when it does not exist there
+pub fn show(root: &Path, rev: &str, path: &str) -> Result<Option<String>> {
+    // `rev:./path` is relative to the root of the monorepo, `rev:path` to the repository
+    let object = format!("{}:./{}", rev, path.trim_start_matches("./"));
+
+    if run(root, &["cat-file", "-e", &object]).is_err() {
+        return Ok(None);
+    }
+
+    run(root, &["show", &object]).map(Some)
+}
+
+/// Files tracked at a revision
+pub fn files(root: &Path, rev: &str) -> Result<Vec<String>> {
+    let output = run(root, &["ls-tree", "-r", "--name-only", rev])?;
+    Ok(output.lines().map(String::from).collect())
+}
+
+/// Files that differ between a revision and the working tree, including
+/// untracked ones
+pub fn changed_files(root: &Path, since: &str) -> Result<Vec<String>> {
+    let changed = run(root, &["diff", "--name-only", "--relative", since])?;
+    let untracked = run(root, &["ls-files", "--others", "--exclude-standard"])?;
+
+    Ok(changed
+        .lines()
+        .chain(untracked.lines())
+        .map(String::from)
+        .collect())
+}
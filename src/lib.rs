@@ -1,14 +1,143 @@
+pub mod analysis;
+pub mod cache;
+pub mod format;
+pub mod git;
 pub mod log;
 pub mod models;
+pub mod monorepo;
 pub mod package_manager;
+pub mod report;
+pub mod rules;
 pub mod utils;
 
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use analysis::Options;
+use clap::{Parser, Subcommand, ValueEnum};
+use models::package_json::Section;
+use models::semantic_version::VersionRange;
+use models::spec::SpecKind;
 use regex::Regex;
+use rules::catalog::Pick;
+
+fn parse_range(value: &str) -> Result<VersionRange, String> {
+    VersionRange::from_str(value).map_err(|_| format!("invalid version range `{}`", value))
+}
 
 #[derive(Subcommand, Clone, Debug)]
 pub enum Action {
-    Search { value: Regex },
+    /// Find the workspaces declaring packages matching the expression
+    Search {
+        value: Regex,
+
+        /// Only show declarations whose lowest allowed version satisfies the range, eg: "<4.17.21"
+        #[clap(long, value_parser = parse_range)]
+        range: Option<VersionRange>,
+
+        /// Only search the given sections (overrides `--dev`, `--peer` and `--prod`)
+        #[clap(long, value_enum)]
+        section: Vec<Section>,
+
+        /// Only show declarations of the given spec kinds, eg: git, file, workspace
+        #[clap(long, value_enum)]
+        kind: Vec<SpecKind>,
+    },
+
+    /// Check that devDependencies satisfy the workspace's own peerDependencies
+    Peers,
+
+    /// List pnpm overrides, yarn resolutions and npm overrides, flagging stale ones
+    Overrides,
+
+    /// Suggest pnpm catalog entries for dependencies shared by many workspaces
+    Catalog {
+        /// Minimum number of workspaces declaring the dependency
+        #[clap(long, default_value_t = 3)]
+        min: usize,
+
+        /// How the catalog version is picked
+        #[clap(long, value_enum, default_value_t = Pick::Highest)]
+        pick: Pick,
+
+        /// Write the catalog to `pnpm-workspace.yaml` and use `catalog:` in the workspaces
+        #[clap(long)]
+        write: bool,
+
+        /// Print the changes `--write` would make without applying them
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Report git, tarball and path dependencies used at different revisions
+    Sources,
+
+    /// Re-run the default check (and `drift` when there is a lockfile) whenever
+    /// a manifest, workspace file or lockfile changes, printing what changed
+    Watch {
+        /// Milliseconds without changes to wait for before re-running
+        #[clap(long, default_value_t = 300)]
+        debounce: u64,
+    },
+
+    /// Compare the dependencies declared at two git revisions
+    Diff {
+        /// Base revision, eg: `main`
+        before: String,
+
+        /// Revision to compare with the base, eg: `HEAD`
+        after: String,
+    },
+
+    /// Report dependencies declared by the manifests that the lockfile does not reflect
+    Drift,
+
+    /// Report packages installed in more than one version anywhere in the lockfile
+    Transitive,
+
+    /// Show how the workspaces depend on a package, using the lockfile
+    Why {
+        package: String,
+
+        /// Only show the chains starting from this workspace
+        #[clap(long)]
+        workspace: Option<String>,
+
+        /// Maximum length of the chains
+        #[clap(long, default_value_t = rules::why::DEFAULT_DEPTH)]
+        depth: usize,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Table,
+    Json,
+    Yaml,
+    Markdown,
+    Sarif,
+    Junit,
+    Github,
+    Html,
+    Csv,
+    Tsv,
+}
+
+/// Order of the reported rows
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sort {
+    /// Alphabetically
+    #[default]
+    Name,
+    /// Most workspaces first
+    Count,
+    /// Most distinct versions first
+    Versions,
+    /// Most severe problems first (eg: version conflicts before duplicates)
+    Severity,
+    /// Largest installed size first
+    Size,
 }
 
 #[derive(Parser, Clone, Debug)]
@@ -51,9 +180,39 @@ pub struct Args {
     #[clap(global = true, long, value_parser)]
     pub yaml: bool,
 
-    #[clap(long, value_parser)]
+    #[clap(global = true, long, value_parser)]
     pub no_color: bool,
 
+    /// Output format (`--json` and `--yaml` are shorthands)
+    #[clap(global = true, long, value_enum)]
+    pub format: Option<Format>,
+
+    /// Sort the reported rows, by name by default (`transitive`: by copies then size)
+    #[clap(global = true, long, value_enum)]
+    pub sort: Option<Sort>,
+
+    /// Reverse the sort order
+    #[clap(global = true, long)]
+    pub reverse: bool,
+
+    /// Only analyze the dependencies declared by workspaces whose package.json
+    /// changed since this git ref, they are still compared with every workspace
+    #[clap(global = true, long)]
+    pub since: Option<String>,
+
+    /// Reuse the manifests and lockfile parsed by previous runs when the files
+    /// did not change
+    #[clap(global = true, long)]
+    pub cache: bool,
+
+    /// Where `--cache` stores the parsed files
+    #[clap(global = true, long, value_parser, default_value = cache::DEFAULT_DIR)]
+    pub cache_dir: PathBuf,
+
+    /// Write the report to a file instead of stdout
+    #[clap(global = true, long, short = 'o', value_parser)]
+    pub output: Option<PathBuf>,
+
     #[clap(global = true, long, short = 'D', value_parser)]
     pub dev: bool,
 
@@ -69,3 +228,52 @@ pub struct Args {
     #[clap(subcommand)]
     pub action: Option<Action>,
 }
+
+impl Args {
+    /// The requested output format, taking `--json` and `--yaml` into account
+    pub fn format(&self) -> Format {
+        match self.format {
+            Some(format) => format,
+            None if self.json => Format::Json,
+            None if self.yaml => Format::Yaml,
+            None => Format::Table,
+        }
+    }
+
+    /// Sections selected by `--prod`, `--dev` and `--peer`, `dependencies` by
+    /// default. `--peer` only applies to the commands checking peers.
+    pub fn sections(&self, peers: bool) -> Vec<Section> {
+        let peer = peers && self.peer;
+        let mut sections = Vec::new();
+
+        if self.prod || !self.dev && !peer {
+            sections.push(Section::Dependencies);
+        }
+
+        if self.dev {
+            sections.push(Section::DevDependencies);
+        }
+
+        if peer {
+            sections.push(Section::PeerDependencies);
+        }
+
+        sections
+    }
+
+    /// Options of the analysis selected by the flags
+    pub fn options(&self) -> Options {
+        Options {
+            sections: self.sections(false),
+            ignore: self.ignore.clone().unwrap_or_default(),
+            ignore_workspace: self.ignore_workspace.clone(),
+            match_workspace: self.match_workspace.clone(),
+            matches: self.matches.clone(),
+            include_root: self.include_root,
+            check_workspace: self.check_workspace,
+            deep: self.deep,
+            min: self.min,
+            since: self.since.clone(),
+        }
+    }
+}
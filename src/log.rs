@@ -1,11 +1,11 @@
 use colored::*;
 
 pub fn warn(message: &str) {
-    println!("[{}] {}", "WARN".yellow().bold(), message)
+    eprintln!("[{}] {}", "WARN".yellow().bold(), message)
 }
 
 pub fn error<E: std::fmt::Debug>(message: &str, err: E) {
-    println!(
+    eprintln!(
         "[{}] {} {:?}",
         "ERROR".white().on_red().bold(),
         message,
@@ -1,470 +1,360 @@
-use std::collections::hash_map::DefaultHasher;
-use std::collections::{HashMap, HashSet};
-use std::hash::{Hash, Hasher};
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
 use clap::Parser;
-use colored_json::ToColoredJson;
-use glob::glob;
-use monocheck::models::file::*;
-use monocheck::models::package_json::PackageJson;
-use monocheck::models::semantic_version::*;
-use monocheck::models::workspace::Workspace;
-
+use colored::Colorize;
+use monocheck::analysis::{Analysis, SearchFilter, Touched};
+use monocheck::cache::Stamp;
+use monocheck::models::lockfile::Lockfile;
+use monocheck::monorepo::Monorepo;
+
+use monocheck::format::{self, Finding, Render};
+use monocheck::report::{Catalog, Diff, Drifts, Overrides, PeerDrifts, Sources, Transitive, Why};
+use monocheck::rules::diff::Snapshot;
+use monocheck::rules::{self, peer_drift};
 use monocheck::{log, Action, Args};
 
-use prettytable::{row, Table};
-use serde::Serialize;
+/// How often `watch` checks the files for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(200);
 
-#[derive(Debug, Serialize, Clone, Default)]
-pub struct Dependency {
-    pub versions: HashSet<String>,
-    pub workspaces: HashSet<String>,
+/// Current stamps of the files watched by `watch`
+fn stamps(files: &BTreeSet<PathBuf>) -> BTreeMap<PathBuf, Option<Stamp>> {
+    files
+        .iter()
+        .map(|path| (path.clone(), Stamp::of(path)))
+        .collect()
 }
 
-impl Dependency {
-    pub fn len(&self) -> usize {
-        self.workspaces.len()
-    }
-
-    pub fn is_empty(&self) -> bool {
-        self.workspaces.is_empty()
-    }
-}
-
-impl Hash for Dependency {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        let mut versions: Vec<_> = self.versions.iter().collect();
-        versions.sort();
-
-        let mut workspaces: Vec<_> = self.workspaces.iter().collect();
-        workspaces.sort();
-
-        // hash the sorted versions and workspaces using `DefaultHasher`
-        let mut v_hasher = DefaultHasher::new();
-        versions.hash(&mut v_hasher);
-        let v_hash = v_hasher.finish();
+/// Findings of the default check, and of `drift` when there is a lockfile,
+/// keyed by rule and title
+fn watch_findings(
+    monorepo: &Monorepo,
+    args: &Args,
+) -> anyhow::Result<BTreeMap<(&'static str, String), Finding>> {
+    let mut findings = Analysis::run(monorepo, &args.options())?
+        .duplicates
+        .findings();
 
-        let mut w_hasher = DefaultHasher::new();
-        workspaces.hash(&mut w_hasher);
-        let w_hash = w_hasher.finish();
+    if Lockfile::find(&monorepo.root).is_some() {
+        let lockfile = monorepo.lockfile(cache_dir(args))?;
+        let drifts = rules::drift::check(&lockfile, monorepo.manifests(true), &monorepo.resolver);
 
-        v_hash.hash(state);
-        w_hash.hash(state);
+        findings.extend(Drifts(drifts).findings());
     }
-}
 
-impl PartialEq for Dependency {
-    fn eq(&self, other: &Self) -> bool {
-        let mut h1 = DefaultHasher::new();
-        self.hash(&mut h1);
-
-        let mut h2 = DefaultHasher::new();
-        other.hash(&mut h2);
-
-        h1.finish() == h2.finish()
-    }
+    Ok(findings
+        .into_iter()
+        .map(|f| ((f.rule, f.title.clone()), f))
+        .collect())
 }
 
-impl Eq for Dependency {}
-
-type DependencyMap = HashMap<String, Dependency>;
-
-fn add_to_dependency_map(
-    map: &mut DependencyMap,
-    package_name: &String,
-    package_version: &String,
-    workspace: &String,
+/// Prints the findings that appeared (`+`), got fixed (`-`) or changed (`~`)
+fn print_changes(
+    before: &BTreeMap<(&'static str, String), Finding>,
+    after: &BTreeMap<(&'static str, String), Finding>,
     args: &Args,
 ) {
-    let version = match package_version.as_ref() {
-        "workspace:^" => "workspace".to_string(),
-        "workspace:*" => "workspace".to_string(),
-        v => v.replace('^', ""),
-    };
-
-    if version == "workspace" && !args.include_root {
-        return;
-    }
-
-    let ignored_pkgs = args.ignore.clone().unwrap_or_default();
-
-    // skip ignored packages
-    if ignored_pkgs.contains(package_name) || args.ignore_workspace.contains(workspace) {
-        return;
-    }
-
-    // search for workspace name matches
-    if let Some(matches) = args.match_workspace.clone() {
-        if !matches.is_match(workspace) {
-            return;
-        }
+    let keys: BTreeSet<_> = before.keys().chain(after.keys()).collect();
+
+    for key in keys {
+        let (sign, finding) = match (before.get(key), after.get(key)) {
+            (None, Some(f)) => ('+', f),
+            (Some(f), None) => ('-', f),
+            (Some(a), Some(b)) if a.message != b.message || a.level != b.level => ('~', b),
+            _ => continue,
+        };
+
+        let line = format!(
+            "{} [{}] {}: {}",
+            sign,
+            finding.level.as_str(),
+            finding.title,
+            finding.message
+        );
+
+        let line = match sign {
+            _ if args.no_color => line.normal(),
+            '+' => line.red(),
+            '-' => line.green(),
+            _ => line.yellow(),
+        };
+
+        println!("{}", line);
     }
+}
 
-    // search for matches
-    if let Some(matches) = args.matches.clone() {
-        if !matches.is_match(package_name) {
-            return;
+/// Polls the watched files, waits for them to settle, then re-runs the checks.
+///
+/// Only the files of the last scan are polled: the workspace patterns are
+/// expanded again by the scan a change triggers, so a new workspace is
+/// picked up on the next change to a watched file.
+fn watch(monorepo: Monorepo, args: &Args, debounce: Duration) -> anyhow::Result<()> {
+    let mut monorepo = monorepo;
+    let mut findings = watch_findings(&monorepo, args)?;
+    let mut files = monorepo.files();
+    let mut known = stamps(&files);
+
+    print_changes(&BTreeMap::new(), &findings, args);
+    println!(
+        "Watching {} files, {} problems found",
+        files.len(),
+        findings.len()
+    );
+
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+
+        let mut current = stamps(&files);
+
+        if current == known {
+            continue;
         }
-    }
-
-    let dependency = map
-        .entry(package_name.to_owned())
-        .or_insert_with(Dependency::default);
-
-    dependency.workspaces.insert(workspace.to_owned());
 
-    dependency.versions.insert(version);
-}
-
-#[derive(Debug, Serialize)]
-struct JSONData {
-    pub name: String,
-    pub count: usize,
-    pub workspaces: Vec<String>,
-    pub versions: Vec<String>,
-}
+        // editors and package managers write several files in a row
+        loop {
+            thread::sleep(debounce);
+            let next = stamps(&files);
 
-fn search_deps(
-    manifest: &PackageJson,
-    map: &mut HashMap<String, HashSet<String>>,
-    args: &Args,
-    value: &regex::Regex,
-) {
-    if args.prod || !args.dev && !args.peer {
-        for (name, version) in manifest.dependencies.0.clone() {
-            // search dependency
-            if !value.is_match(&name) {
-                continue;
+            if next == current {
+                break;
             }
 
-            let version = match version.as_ref() {
-                "workspace:^" => "workspace".to_string(),
-                "workspace:*" => "workspace".to_string(),
-                v => v.replace('^', ""),
-            };
-
-            let pkg_name = if args.deep {
-                format!("{}@{}", name, version)
-            } else {
-                name
-            };
-
-            map.entry(pkg_name)
-                .or_insert_with(HashSet::new)
-                .insert(manifest.name.clone());
+            current = next;
         }
-    }
 
-    if args.dev {
-        for (name, version) in manifest.dev_dependencies.0.clone() {
-            // search dependency
-            if !value.is_match(&name) {
-                continue;
-            }
+        let result = Monorepo::scan(&monorepo.root, cache_dir(args))
+            .map_err(anyhow::Error::from)
+            .and_then(|m| watch_findings(&m, args).map(|findings| (m, findings)));
 
-            let version = match version.as_ref() {
-                "workspace:^" => "workspace".to_string(),
-                "workspace:*" => "workspace".to_string(),
-                v => v.replace('^', ""),
-            };
-
-            let pkg_name = if args.deep {
-                format!("{}@{}", name, version)
-            } else {
-                name
-            };
-
-            map.entry(pkg_name)
-                .or_insert_with(HashSet::new)
-                .insert(manifest.name.clone());
-        }
-    }
+        match result {
+            Ok((next, next_findings)) => {
+                print_changes(&findings, &next_findings, args);
 
-    if args.peer {
-        for (name, version) in manifest.peer_dependencies.0.clone() {
-            // search dependency
-            if !value.is_match(&name) {
-                continue;
+                monorepo = next;
+                findings = next_findings;
+                files = monorepo.files();
             }
-
-            let version = match version.as_ref() {
-                "workspace:^" => "workspace".to_string(),
-                "workspace:*" => "workspace".to_string(),
-                v => v.replace('^', ""),
-            };
-
-            let pkg_name = if args.deep {
-                format!("{}@{}", name, version)
-            } else {
-                name
-            };
-
-            map.entry(pkg_name)
-                .or_insert_with(HashSet::new)
-                .insert(manifest.name.clone());
+            Err(e) => log::error("Cannot check the monorepo", e),
         }
+
+        known = stamps(&files);
     }
 }
 
-#[derive(Debug, Default, Copy, Clone)]
-enum MonorepoKind {
-    #[default]
-    Generic,
-    Pnpm,
+/// Where `--cache` stores the parsed files, `None` without `--cache`
+fn cache_dir(args: &Args) -> Option<&Path> {
+    args.cache.then_some(args.cache_dir.as_path())
 }
 
-fn get_monorepo_kind() -> Option<MonorepoKind> {
-    let workspace_file = Path::new("pnpm-workspace.yaml");
-    let pkg_file = Path::new("package.json");
+/// Prints a report in the requested format, or writes it to `--output`
+fn print_report<R: Render>(mut report: R, args: &Args) -> anyhow::Result<()> {
+    let sort = args.sort.unwrap_or_else(|| report.default_sort());
+    report.sort(sort, args.reverse);
 
-    if !workspace_file.exists() {
-        if pkg_file.exists() {
-            return Some(MonorepoKind::Generic);
-        }
+    let color = !args.no_color && args.output.is_none();
+    let output = format::render(&report, args.format(), color)?;
 
-        log::error("Workspace file not found", pkg_file);
-        return None;
+    match &args.output {
+        Some(path) => std::fs::write(path, output)?,
+        None => print!("{}", output),
     }
 
-    Some(MonorepoKind::Pnpm)
-}
-
-fn get_packages(kind: MonorepoKind) -> Vec<String> {
-    match kind {
-        MonorepoKind::Generic => {
-            let pkg = Path::new("package.json");
-            let PackageJson { workspaces, .. } = PackageJson::load(pkg).unwrap();
-
-            workspaces.unwrap_or_default()
-        }
-        MonorepoKind::Pnpm => {
-            let workspace_file = Path::new("pnpm-workspace.yaml");
-            let Workspace { packages } = Workspace::load(workspace_file).unwrap();
-
-            packages
-        }
-    }
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let kind = match get_monorepo_kind() {
-        Some(k) => k,
-        None => panic!("Workspace file not found"),
-    };
+    let options = args.options();
+    let monorepo = Monorepo::scan(Path::new("."), cache_dir(&args))?;
 
-    log::error("Workspace file found", kind);
+    log::error("Workspace file found", monorepo.kind);
 
     match args.action.clone() {
         Some(action) => match action {
-            Action::Search { value } => {
-                // prints to the stdout where the package is installed.
-                // HashMap<package_name, HashSet<workspace_name>>
-                let mut dependencies: HashMap<String, HashSet<String>> = HashMap::new();
-
-                let packages = get_packages(kind);
-                let root_manifest = PackageJson::load(Path::new("package.json"))?;
-
-                if args.include_root {
-                    search_deps(&root_manifest, &mut dependencies, &args, &value);
-                }
-
-                for g in packages {
-                    for entry in glob(&g)? {
-                        let path = entry?;
-                        let package_json_path = path.join("package.json");
+            Action::Search {
+                value,
+                range,
+                section,
+                kind: kinds,
+            } => {
+                let filter = SearchFilter {
+                    value,
+                    range,
+                    sections: match section.is_empty() {
+                        true => args.sections(true),
+                        false => section,
+                    },
+                    kinds,
+                };
 
-                        let manifest = PackageJson::load(&package_json_path)?;
+                print_report(Analysis::search(&monorepo, &options, &filter)?, &args)?;
+            }
+            Action::Peers => {
+                let touched = Touched::since(&monorepo, options.since.as_deref())?;
 
-                        search_deps(&manifest, &mut dependencies, &args, &value);
-                    }
-                }
+                let drifts = monorepo
+                    .manifests(args.include_root)
+                    .iter()
+                    .flat_map(|m| peer_drift::check(m, &monorepo.resolver))
+                    .filter(|d| Touched::contains(&touched, &d.dependency))
+                    .collect();
 
-                for (pkg_name, workspaces) in dependencies {
-                    #[derive(Serialize, Debug)]
-                    struct Data {
-                        name: String,
-                        workspaces: Vec<String>,
-                    }
+                print_report(PeerDrifts(drifts), &args)?;
+            }
+            Action::Overrides => {
+                let lockfile = match Lockfile::find(&monorepo.root) {
+                    Some(_) => Some(monorepo.lockfile(cache_dir(&args))?),
+                    None => None,
+                };
 
-                    let data = Data {
-                        name: pkg_name,
-                        workspaces: workspaces.into_iter().collect(),
-                    };
+                let usages = rules::overrides::check(
+                    &monorepo.resolver.overrides,
+                    monorepo.manifests(true),
+                    lockfile.as_ref(),
+                );
 
-                    println!("{}", serde_json::to_string(&data)?.to_colored_json_auto()?);
-                }
+                print_report(Overrides(usages), &args)?;
             }
-        },
-        None => {
-            let packages = get_packages(kind);
-            let mut dependency_map: DependencyMap = DependencyMap::new();
-
-            for g in packages {
-                for entry in glob(&g)? {
-                    let path = entry?;
-                    let package_json_path = path.join("package.json");
-
-                    let pkg = PackageJson::load(&package_json_path)?;
-
-                    let name = &pkg.name;
-
-                    if args.prod || !args.dev {
-                        for (pkg_name, version) in pkg.dependencies.0 {
-                            add_to_dependency_map(
-                                &mut dependency_map,
-                                &pkg_name,
-                                &version,
-                                name,
-                                &args,
-                            );
-                        }
-                    }
+            Action::Sources => {
+                let touched = Touched::since(&monorepo, options.since.as_deref())?;
+                let manifests = monorepo.manifests(args.include_root);
 
-                    if args.dev {
-                        for (pkg_name, version) in pkg.dev_dependencies.0 {
-                            add_to_dependency_map(
-                                &mut dependency_map,
-                                &pkg_name,
-                                &version,
-                                name,
-                                &args,
-                            );
-                        }
-                    }
-                }
+                let conflicts = rules::sources::check(manifests, &monorepo.resolver)
+                    .into_iter()
+                    .filter(|c| {
+                        c.dependencies
+                            .iter()
+                            .any(|d| Touched::contains(&touched, d))
+                    })
+                    .collect();
+
+                print_report(Sources(conflicts), &args)?;
             }
-
-            if args.check_workspace {
-                let pkg = PackageJson::load(Path::new("./package.json"))?;
-
-                let name = &pkg.name;
-
-                if args.prod || !args.dev {
-                    for (pkg_name, version) in pkg.dependencies.0 {
-                        add_to_dependency_map(
-                            &mut dependency_map,
-                            &pkg_name,
-                            &version,
-                            name,
-                            &args,
-                        );
-                    }
-                }
-
-                if args.dev {
-                    for (pkg_name, version) in pkg.dev_dependencies.0 {
-                        add_to_dependency_map(
-                            &mut dependency_map,
-                            &pkg_name,
-                            &version,
-                            name,
-                            &args,
-                        );
-                    }
-                }
+            Action::Watch { debounce } => {
+                return watch(monorepo, &args, Duration::from_millis(debounce));
             }
-
-            // raw output
-            if args.json || args.yaml {
-                // update result with array of packages taht have keys: name, workspaces and count
-                let mut result: Vec<JSONData> = Vec::new();
-
-                for (name, packages) in dependency_map {
-                    let count = packages.len();
-
-                    // ingore --min when deep is true
-                    if count < args.min {
-                        continue;
-                    }
-
-                    let mut workspaces = packages
-                        .clone()
-                        .workspaces
-                        .into_iter()
-                        .collect::<Vec<String>>();
-
-                    workspaces.sort();
-
-                    let mut versions = packages
-                        .clone()
-                        .versions
-                        .into_iter()
-                        .map(SemanticVersion::from)
-                        .collect::<Vec<SemanticVersion>>();
-
-                    versions.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-                    result.push(JSONData {
-                        name,
-                        count,
-                        workspaces,
-                        versions: versions.iter().map(|v| v.to_string()).collect(),
-                    });
-                }
-
-                let string = if args.yaml {
-                    serde_yaml::to_string(&result)?
-                } else if args.no_color {
-                    serde_json::to_string_pretty(&result)?
-                } else {
-                    serde_json::to_string_pretty(&result)?.to_colored_json_auto()?
-                };
-
-                println!("{}", string);
-                return Ok(());
+            Action::Diff { before, after } => {
+                let old = Monorepo::at_revision(&monorepo.root, &before)?;
+                let new = Monorepo::at_revision(&monorepo.root, &after)?;
+
+                let changes = rules::diff::compare(
+                    &Snapshot {
+                        manifests: old.manifests(options.include_root),
+                        resolver: &old.resolver,
+                    },
+                    &Snapshot {
+                        manifests: new.manifests(options.include_root),
+                        resolver: &new.resolver,
+                    },
+                    &options,
+                );
+
+                print_report(
+                    Diff {
+                        before,
+                        after,
+                        changes,
+                    },
+                    &args,
+                )?;
             }
+            Action::Drift => {
+                let lockfile = monorepo.lockfile(cache_dir(&args))?;
+                let drifts =
+                    rules::drift::check(&lockfile, monorepo.manifests(true), &monorepo.resolver);
 
-            // pretty print as table
-            let mut table = Table::new();
-
-            if args.deep {
-                table.add_row(row!["Dependency", "Count", "Versions", "Workspaces"]);
-            } else {
-                table.add_row(row!["Dependency", "Count", "Packages"]);
+                print_report(Drifts(drifts), &args)?;
             }
+            Action::Transitive => {
+                let lockfile = monorepo.lockfile(cache_dir(&args))?;
+                let workspaces = monorepo.importers();
 
-            for (name, packages) in dependency_map.iter() {
-                let count = packages.len();
-
-                if count < args.min {
-                    continue;
-                }
+                let duplicates = rules::transitive::check(&lockfile, &workspaces)
+                    .into_iter()
+                    .filter(|d| options.accepts(&d.name))
+                    .collect();
 
-                let mut workspaces = packages
-                    .clone()
-                    .workspaces
+                print_report(Transitive(duplicates), &args)?;
+            }
+            Action::Why {
+                package,
+                workspace,
+                depth,
+            } => {
+                let lockfile = monorepo.lockfile(cache_dir(&args))?;
+                let workspaces = monorepo.importers();
+
+                let chains = rules::why::explain(&lockfile, &workspaces, &package, depth)
                     .into_iter()
-                    .collect::<Vec<String>>();
+                    .filter(|c| {
+                        workspace
+                            .as_ref()
+                            .map(|w| &c.workspace == w)
+                            .unwrap_or(true)
+                    })
+                    .collect();
+
+                print_report(Why::new(package, chains), &args)?;
+            }
+            Action::Catalog {
+                min,
+                pick,
+                write,
+                dry_run,
+            } => {
+                let workspace = match &monorepo.workspace {
+                    Some(w) => w,
+                    None => {
+                        log::warn("Catalogs are only supported by pnpm workspaces");
+                        return Ok(());
+                    }
+                };
 
-                workspaces.sort();
+                let manifests = monorepo.manifests(false);
+                let touched = Touched::since(&monorepo, options.since.as_deref())?;
 
-                let versions_count = packages
-                    .clone()
-                    .versions
+                let suggestions: Vec<_> = rules::catalog::check(manifests, workspace, min, pick)
                     .into_iter()
-                    .collect::<Vec<String>>()
-                    .len();
-
-                if args.deep {
-                    table.add_row(row![name, count, versions_count, workspaces.join(", ")]);
-                } else {
-                    table.add_row(row![name, count, workspaces.join(", ")]);
-                }
-            }
+                    .filter(|s| Touched::contains(&touched, &s.dependency))
+                    .collect();
+
+                if write || dry_run {
+                    let changes = rules::catalog::migrate(
+                        &suggestions,
+                        manifests,
+                        &monorepo.path("pnpm-workspace.yaml"),
+                    )?;
+
+                    for change in changes.iter() {
+                        for line in change.diff().lines() {
+                            let line = match line.chars().next() {
+                                _ if args.no_color => line.normal(),
+                                Some('+') => line.green(),
+                                Some('-') => line.red(),
+                                Some('@') => line.cyan(),
+                                _ => line.normal(),
+                            };
+
+                            println!("{}", line);
+                        }
 
-            table.printstd();
+                        if !dry_run {
+                            change.apply()?;
+                        }
+                    }
 
-            // -1 because we have to remove header row
-            let total = table.row_iter().len() - 1;
+                    let verb = if dry_run { "Would update" } else { "Updated" };
+                    println!("{} {} file(s)", verb, changes.len());
+                    return Ok(());
+                }
 
-            if total == 0 {
-                println!("No duplicate dependencies found (min: {})", args.min);
-                return Ok(());
+                print_report(Catalog { suggestions, min }, &args)?;
             }
-
-            println!("Total : {}", total);
+        },
+        None => {
+            print_report(Analysis::run(&monorepo, &options)?.duplicates, &args)?;
         }
     }
 
@@ -1,11 +1,11 @@
 use serde::{de, Serialize};
-use std::{fmt, fs, io, path::Path};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
 
 pub type Result<T> = std::result::Result<T, FileError>;
 
-#[derive(Debug, Clone)]
-struct PackageJsonError;
-
 #[derive(Debug, Clone)]
 pub enum FileKind {
     JSON,
@@ -32,19 +32,7 @@ where
 {
     /// Reads file from filesystem. It must be json or yaml.
     fn load(path: &Path) -> Result<T> {
-        let r = fs::File::open(path)?;
-        let kind = FileKind::from_path(path)?;
-
-        match kind {
-            FileKind::JSON => match serde_json::from_reader(r) {
-                Ok(d) => Ok(d),
-                Err(e) => Err(FileError::from(e)),
-            },
-            FileKind::YAML => match serde_yaml::from_reader(r) {
-                Ok(d) => Ok(d),
-                Err(e) => Err(FileError::from(e)),
-            },
-        }
+        read(path)
     }
 
     fn write(&self, path: &Path) -> Result<()>
@@ -63,12 +51,60 @@ where
     }
 }
 
+/// Deserializes a json or yaml file
+pub fn read<T: de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let r = fs::File::open(path)?;
+    let kind = FileKind::from_path(path)?;
+
+    match kind {
+        FileKind::JSON => match serde_json::from_reader(r) {
+            Ok(d) => Ok(d),
+            Err(e) => Err(FileError::from(e)),
+        },
+        FileKind::YAML => match serde_yaml::from_reader(r) {
+            Ok(d) => Ok(d),
+            Err(e) => Err(FileError::from(e)),
+        },
+    }
+}
+
+/// Pending modification of a text file
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+impl FileChange {
+    pub fn is_empty(&self) -> bool {
+        self.before == self.after
+    }
+
+    /// Unified diff between the current and the new content
+    pub fn diff(&self) -> String {
+        let path = self.path.display().to_string();
+
+        similar::TextDiff::from_lines(&self.before, &self.after)
+            .unified_diff()
+            .header(&format!("a/{}", path), &format!("b/{}", path))
+            .to_string()
+    }
+
+    pub fn apply(&self) -> Result<()> {
+        fs::write(&self.path, &self.after)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum FileError {
     NotFound,
     UnsupportedExtension,
     InvalidYAMLSyntax(serde_yaml::Error),
     InvalidJSONSyntax(serde_json::Error),
+    InvalidLockfile(String),
+    UnsupportedWorkspace(String),
     IO(io::Error),
 }
 
@@ -81,6 +117,10 @@ impl fmt::Display for FileError {
             }
             FileError::InvalidYAMLSyntax(err) => format!("Invalid YAML syntax: {:?}", err),
             FileError::InvalidJSONSyntax(err) => format!("Invalid JSON syntax: {:?}", err),
+            FileError::InvalidLockfile(err) => format!("Invalid lockfile: {}", err),
+            FileError::UnsupportedWorkspace(err) => {
+                format!("Cannot update the workspace file: {}", err)
+            }
             FileError::IO(err) => format!("{}", err),
         };
 
@@ -0,0 +1,286 @@
+//! Lockfiles of the supported package managers, reduced to a single
+//! dependency graph: importers (the workspaces) depend on locked packages,
+//! which in turn depend on other locked packages or workspaces.
+
+pub mod npm;
+pub mod pnpm;
+pub mod yarn;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::file::{self, File, FileError};
+use super::package_json::{PackageJson, Section};
+use super::spec::normalize_path;
+
+/// Importer path of the workspace at the root of the monorepo
+pub const ROOT_IMPORTER: &str = ".";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockfileKind {
+    Pnpm,
+    Npm,
+    Yarn,
+}
+
+impl LockfileKind {
+    pub const ALL: [LockfileKind; 3] = [LockfileKind::Pnpm, LockfileKind::Npm, LockfileKind::Yarn];
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            LockfileKind::Pnpm => "pnpm-lock.yaml",
+            LockfileKind::Npm => "package-lock.json",
+            LockfileKind::Yarn => "yarn.lock",
+        }
+    }
+}
+
+impl fmt::Display for LockfileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file_name())
+    }
+}
+
+/// What a locked dependency resolves to
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "type", content = "id")]
+pub enum Target {
+    /// A key of [`Lockfile::packages`]
+    Package(String),
+    /// The importer path of a workspace, eg: `packages/a`
+    Workspace(String),
+}
+
+/// A direct dependency of an importer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedDependency {
+    /// The spec the lockfile was generated from, when recorded
+    pub specifier: Option<String>,
+
+    /// `None` when the lockfile does not contain the resolved package
+    pub target: Option<Target>,
+}
+
+/// A workspace of the lockfile with its direct dependencies
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Importer {
+    pub dependencies: BTreeMap<Section, BTreeMap<String, LockedDependency>>,
+}
+
+impl Importer {
+    pub fn get(&self, section: Section, name: &str) -> Option<&LockedDependency> {
+        self.dependencies.get(&section)?.get(name)
+    }
+
+    /// Every direct dependency with its section
+    pub fn iter(&self) -> impl Iterator<Item = (Section, &String, &LockedDependency)> {
+        self.dependencies
+            .iter()
+            .flat_map(|(section, deps)| deps.iter().map(move |(name, dep)| (*section, name, dep)))
+    }
+}
+
+/// A package installed from the lockfile
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+
+    /// Dependency name => what it resolves to
+    pub dependencies: BTreeMap<String, Target>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub kind: LockfileKind,
+    pub path: PathBuf,
+
+    /// Importer path (`.` for the root) => direct dependencies
+    pub importers: BTreeMap<String, Importer>,
+
+    pub packages: BTreeMap<String, LockedPackage>,
+
+    /// Yarn only: `name@range` descriptors => resolved target, used to build
+    /// the importers from the manifests since yarn does not record them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub descriptors: BTreeMap<String, Target>,
+
+    /// pnpm only: the overrides the lockfile was generated with, key => version
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, String>,
+}
+
+impl Lockfile {
+    pub fn new(kind: LockfileKind, path: &Path) -> Self {
+        Self {
+            kind,
+            path: path.to_path_buf(),
+            importers: BTreeMap::new(),
+            packages: BTreeMap::new(),
+            descriptors: BTreeMap::new(),
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    /// The first lockfile found in `root`
+    pub fn find(root: &Path) -> Option<PathBuf> {
+        LockfileKind::ALL
+            .iter()
+            .map(|kind| root.join(kind.file_name()))
+            .find(|path| path.is_file())
+    }
+
+    /// Parses a lockfile, the package manager is inferred from the file name
+    pub fn load(path: &Path) -> file::Result<Lockfile> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+
+        match name {
+            "pnpm-lock.yaml" => pnpm::load(path),
+            "package-lock.json" | "npm-shrinkwrap.json" => npm::load(path),
+            "yarn.lock" => yarn::load(path),
+            _ => Err(FileError::UnsupportedExtension),
+        }
+    }
+
+    /// Fills the importers of lockfiles that do not record them (yarn) from
+    /// the dependencies declared by the manifests.
+    pub fn link_manifests(&mut self, manifests: &[PackageJson]) {
+        if self.descriptors.is_empty() {
+            return;
+        }
+
+        // yarn classic does not lock workspaces, they are matched by name
+        let workspaces: BTreeMap<&String, String> = manifests
+            .iter()
+            .map(|m| (&m.name, self.importer(&m.path)))
+            .collect();
+
+        for manifest in manifests {
+            let mut importer = Importer::default();
+
+            for section in [Section::Dependencies, Section::DevDependencies] {
+                for (name, spec) in manifest.section(section).0.iter() {
+                    let target = yarn::descriptor_keys(name, spec)
+                        .iter()
+                        .find_map(|key| self.descriptors.get(key))
+                        .cloned()
+                        .or_else(|| workspaces.get(name).cloned().map(Target::Workspace));
+
+                    importer.dependencies.entry(section).or_default().insert(
+                        name.clone(),
+                        LockedDependency {
+                            specifier: Some(spec.clone()),
+                            target,
+                        },
+                    );
+                }
+            }
+
+            let path = self.importer(&manifest.path);
+            self.importers.insert(path, importer);
+        }
+    }
+
+    /// Importer path of a manifest, relative to the directory of the lockfile
+    pub fn importer(&self, manifest: &Path) -> String {
+        let root = self.path.parent().unwrap_or(Path::new(""));
+        importer_path(manifest.strip_prefix(root).unwrap_or(manifest))
+    }
+
+    /// Name and version of a target, using the importer path for workspaces
+    pub fn describe(&self, target: &Target) -> (String, String) {
+        match target {
+            Target::Package(id) => match self.packages.get(id) {
+                Some(package) => (package.name.clone(), package.version.clone()),
+                None => (id.clone(), String::new()),
+            },
+            Target::Workspace(path) => (path.clone(), "workspace".to_string()),
+        }
+    }
+
+    /// Directory a package is installed at, when `node_modules` is present
+    pub fn install_dir(&self, id: &str) -> Option<PathBuf> {
+        let root = self.path.parent().unwrap_or(Path::new(""));
+        let package = self.packages.get(id)?;
+
+        let dir = match self.kind {
+            LockfileKind::Npm => root.join(id),
+            LockfileKind::Pnpm => root
+                .join("node_modules/.pnpm")
+                .join(pnpm::store_dir(id))
+                .join("node_modules")
+                .join(&package.name),
+            // only hoisted packages can be located without the install state
+            LockfileKind::Yarn => root.join("node_modules").join(&package.name),
+        };
+
+        let installed = PackageJson::load(&dir.join("package.json")).ok()?;
+
+        match installed.version.as_deref() == Some(package.version.as_str()) {
+            true => Some(dir),
+            false => None,
+        }
+    }
+
+    /// Dependencies of a node of the graph
+    pub fn dependencies(&self, target: &Target) -> Vec<(Option<Section>, String, Target)> {
+        match target {
+            Target::Package(id) => self
+                .packages
+                .get(id)
+                .map(|p| {
+                    p.dependencies
+                        .iter()
+                        .map(|(name, target)| (None, name.clone(), target.clone()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Target::Workspace(path) => self
+                .importers
+                .get(path)
+                .map(|importer| {
+                    importer
+                        .iter()
+                        .filter_map(|(section, name, dep)| {
+                            dep.target.clone().map(|t| (Some(section), name.clone(), t))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Importer path of a manifest, relative to the root of the monorepo
+pub fn importer_path(manifest: &Path) -> String {
+    let dir = manifest.parent().unwrap_or(Path::new(""));
+    let dir = normalize_path(dir).to_string_lossy().replace('\\', "/");
+
+    match dir.is_empty() {
+        true => ROOT_IMPORTER.to_string(),
+        false => dir,
+    }
+}
+
+/// Joins a relative path to an importer path, eg: `packages/a` + `../b`
+pub fn join_importer(base: &str, relative: &str) -> String {
+    let base = match base {
+        ROOT_IMPORTER => Path::new(""),
+        base => Path::new(base),
+    };
+
+    importer_path(&normalize_path(&base.join(relative)).join("package.json"))
+}
+
+/// Splits `name@version` taking care of scoped packages
+pub fn split_name_version(s: &str) -> Option<(&str, &str)> {
+    let at = s.char_indices().skip(1).find(|(_, c)| *c == '@')?.0;
+    Some((&s[..at], &s[at + 1..]))
+}
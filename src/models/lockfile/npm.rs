@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use super::{
+    Importer, LockedDependency, LockedPackage, Lockfile, LockfileKind, Target, ROOT_IMPORTER,
+};
+use crate::models::file::{self, FileError};
+use crate::models::package_json::Section;
+
+const NODE_MODULES: &str = "node_modules/";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageLock {
+    #[serde(default)]
+    lockfile_version: usize,
+
+    /// Install path (`node_modules/foo`, `packages/a`...) => entry
+    #[serde(default)]
+    packages: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    name: Option<String>,
+    version: Option<String>,
+
+    /// Target of a symlinked workspace
+    resolved: Option<String>,
+
+    #[serde(default)]
+    link: bool,
+
+    #[serde(default)]
+    dependencies: BTreeMap<String, String>,
+
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, String>,
+
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, String>,
+
+    #[serde(default)]
+    peer_dependencies: BTreeMap<String, String>,
+}
+
+pub fn load(path: &Path) -> file::Result<Lockfile> {
+    let text = fs::read_to_string(path)?;
+    let lock: PackageLock = serde_json::from_str(&text)?;
+
+    if lock.lockfile_version < 2 {
+        return Err(FileError::InvalidLockfile(format!(
+            "{} uses lockfileVersion {}, run `npm install` to upgrade it to v2 or later",
+            path.display(),
+            lock.lockfile_version
+        )));
+    }
+
+    Ok(parse(&lock, path))
+}
+
+/// Name of the package installed at `path`, eg: `a/node_modules/@scope/b` => `@scope/b`
+fn install_name(path: &str) -> &str {
+    match path.rfind(NODE_MODULES) {
+        Some(i) => &path[i + NODE_MODULES.len()..],
+        None => path,
+    }
+}
+
+/// Node's module resolution: looks for `name` in the `node_modules` of `from`
+/// and of each of its parents.
+fn resolve(lock: &PackageLock, from: &str, name: &str) -> Option<Target> {
+    let mut base = from.trim_end_matches('/').to_string();
+
+    loop {
+        let candidate = match base.is_empty() {
+            true => format!("{}{}", NODE_MODULES, name),
+            false => format!("{}/{}{}", base, NODE_MODULES, name),
+        };
+
+        if let Some(entry) = lock.packages.get(&candidate) {
+            return match (entry.link, &entry.resolved) {
+                (true, Some(resolved)) => Some(Target::Workspace(resolved.clone())),
+                _ => Some(Target::Package(candidate)),
+            };
+        }
+
+        if base.is_empty() {
+            return None;
+        }
+
+        base = match base.rfind(NODE_MODULES) {
+            Some(i) => base[..i].trim_end_matches('/').to_string(),
+            None => String::new(),
+        };
+    }
+}
+
+fn parse(lock: &PackageLock, path: &Path) -> Lockfile {
+    let mut lockfile = Lockfile::new(LockfileKind::Npm, path);
+
+    for (key, entry) in lock.packages.iter() {
+        if entry.link {
+            continue;
+        }
+
+        let is_package = key.starts_with(NODE_MODULES) || key.contains("/node_modules/");
+
+        if !is_package {
+            // the root (`""`) or a workspace
+            let importer_path = match key.is_empty() {
+                true => ROOT_IMPORTER.to_string(),
+                false => key.clone(),
+            };
+
+            let mut importer = Importer::default();
+            let sections = [
+                (&entry.dependencies, Section::Dependencies),
+                (&entry.optional_dependencies, Section::Dependencies),
+                (&entry.dev_dependencies, Section::DevDependencies),
+                (&entry.peer_dependencies, Section::PeerDependencies),
+            ];
+
+            for (dependencies, section) in sections {
+                for (name, specifier) in dependencies {
+                    importer.dependencies.entry(section).or_default().insert(
+                        name.clone(),
+                        LockedDependency {
+                            specifier: Some(specifier.clone()),
+                            target: resolve(lock, key, name),
+                        },
+                    );
+                }
+            }
+
+            lockfile.importers.insert(importer_path, importer);
+            continue;
+        }
+
+        let mut package = LockedPackage {
+            name: entry
+                .name
+                .clone()
+                .unwrap_or_else(|| install_name(key).to_string()),
+            version: entry.version.clone().unwrap_or_default(),
+            ..Default::default()
+        };
+
+        let dependencies = entry
+            .dependencies
+            .keys()
+            .chain(entry.optional_dependencies.keys())
+            .chain(entry.peer_dependencies.keys());
+
+        for name in dependencies {
+            if let Some(target) = resolve(lock, key, name) {
+                package.dependencies.insert(name.clone(), target);
+            }
+        }
+
+        lockfile.packages.insert(key.clone(), package);
+    }
+
+    lockfile
+}
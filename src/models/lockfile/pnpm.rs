@@ -0,0 +1,246 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use serde_yaml::{Mapping, Value};
+
+use super::{
+    join_importer, split_name_version, Importer, LockedDependency, LockedPackage, Lockfile,
+    LockfileKind, Target, ROOT_IMPORTER,
+};
+use crate::models::file;
+use crate::models::package_json::Section;
+
+/// Layouts of `pnpm-lock.yaml` that differ in how packages are keyed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Version {
+    /// `/name/1.0.0_peer@1.0.0`
+    V5,
+    /// `/name@1.0.0(peer@1.0.0)`
+    V6,
+    /// `name@1.0.0(peer@1.0.0)`, dependencies live in `snapshots`
+    V9,
+}
+
+const SECTIONS: [(&str, Section); 3] = [
+    ("dependencies", Section::Dependencies),
+    ("devDependencies", Section::DevDependencies),
+    ("optionalDependencies", Section::Dependencies),
+];
+
+pub fn load(path: &Path) -> file::Result<Lockfile> {
+    let text = fs::read_to_string(path)?;
+    let value: Value = serde_yaml::from_str(&text)?;
+
+    Ok(parse(&value, path))
+}
+
+fn version(value: &Value) -> Version {
+    let raw = match value.get("lockfileVersion") {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => return Version::V9,
+    };
+
+    match raw.split('.').next().and_then(|m| m.parse::<usize>().ok()) {
+        Some(5) => Version::V5,
+        Some(6..=8) => Version::V6,
+        _ => Version::V9,
+    }
+}
+
+fn mapping<'a>(value: &'a Value, key: &str) -> Option<&'a Mapping> {
+    value.get(key).and_then(Value::as_mapping)
+}
+
+fn string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Removes the `(peer@1.0.0)` suffix of v6+ keys and versions
+fn strip_peers(s: &str) -> &str {
+    s.split('(').next().unwrap_or(s)
+}
+
+/// `1.0.0_peer@1.0.0` but not `a@1.0.0` nor `3d@1.0.0`
+fn is_v5_version(s: &str) -> bool {
+    let version = s.split('_').next().unwrap_or(s);
+    version.starts_with(|c: char| c.is_ascii_digit()) && !version.contains('@')
+}
+
+/// Directory of a package in `node_modules/.pnpm`, eg: `/@scope/a@1.0.0(b@2.0.0)`
+/// => `@scope+a@1.0.0_b@2.0.0`
+pub fn store_dir(key: &str) -> String {
+    let key = key.strip_prefix('/').unwrap_or(key);
+
+    // v5 keys separate the name from the version with a slash, peers use `+`
+    let key = match key.rsplit_once('/') {
+        Some((name, version)) if is_v5_version(version) => format!("{}@{}", name, version),
+        _ => key.to_string(),
+    };
+
+    key.replace('/', "+").replace('(', "_").replace(')', "")
+}
+
+/// Name and version encoded in a package key
+fn parse_key(key: &str, version: Version) -> Option<(String, String)> {
+    let (name, version) = match version {
+        Version::V5 => {
+            let (name, version) = key.strip_prefix('/')?.rsplit_once('/')?;
+            (name, version.split('_').next().unwrap_or(version))
+        }
+        Version::V6 => split_name_version(strip_peers(key).strip_prefix('/')?)?,
+        Version::V9 => split_name_version(strip_peers(key))?,
+    };
+
+    Some((name.to_string(), version.to_string()))
+}
+
+/// Finds the package key a `name: version` reference points at
+fn resolve(
+    keys: &BTreeSet<String>,
+    version: Version,
+    importer: &str,
+    name: &str,
+    reference: &str,
+) -> Option<Target> {
+    if let Some(path) = reference.strip_prefix("link:") {
+        return Some(Target::Workspace(join_importer(importer, path)));
+    }
+
+    let candidates = match version {
+        _ if reference.starts_with('/') => vec![reference.to_string()],
+        Version::V5 => vec![format!("/{}/{}", name, reference), reference.to_string()],
+        Version::V6 => vec![
+            format!("/{}@{}", name, reference),
+            format!("/{}", reference),
+            reference.to_string(),
+        ],
+        Version::V9 => vec![format!("{}@{}", name, reference), reference.to_string()],
+    };
+
+    candidates
+        .into_iter()
+        .find(|key| keys.contains(key))
+        .map(Target::Package)
+}
+
+fn parse(value: &Value, path: &Path) -> Lockfile {
+    let version = version(value);
+    let mut lockfile = Lockfile::new(LockfileKind::Pnpm, path);
+
+    let nodes = match version {
+        Version::V9 => mapping(value, "snapshots"),
+        _ => mapping(value, "packages"),
+    };
+
+    // v9 keeps the metadata (name, version of git dependencies...) apart
+    let metadata = mapping(value, "packages");
+
+    let keys: BTreeSet<String> = nodes
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, _)| string(key))
+        .collect();
+
+    for (key, entry) in nodes.into_iter().flatten() {
+        let key = match string(key) {
+            Some(key) => key,
+            None => continue,
+        };
+
+        let meta = metadata
+            .and_then(|m| m.get(strip_peers(&key)))
+            .unwrap_or(entry);
+
+        let (mut name, mut package_version) = parse_key(&key, version).unwrap_or_default();
+
+        if let Some(n) = meta.get("name").and_then(string) {
+            name = n;
+        }
+
+        if let Some(v) = meta.get("version").and_then(string) {
+            package_version = v;
+        }
+
+        let mut package = LockedPackage {
+            name,
+            version: package_version,
+            ..Default::default()
+        };
+
+        for field in ["dependencies", "optionalDependencies"] {
+            for (dep, reference) in mapping(entry, field).into_iter().flatten() {
+                let (dep, reference) = match (string(dep), string(reference)) {
+                    (Some(d), Some(r)) => (d, r),
+                    _ => continue,
+                };
+
+                if let Some(target) = resolve(&keys, version, ROOT_IMPORTER, &dep, &reference) {
+                    package.dependencies.insert(dep, target);
+                }
+            }
+        }
+
+        lockfile.packages.insert(key, package);
+    }
+
+    // lockfiles of single package projects have no `importers`
+    let importers: Vec<(String, &Value)> = match mapping(value, "importers") {
+        Some(importers) => importers
+            .iter()
+            .filter_map(|(path, importer)| Some((string(path)?, importer)))
+            .collect(),
+        None => vec![(ROOT_IMPORTER.to_string(), value)],
+    };
+
+    for (path, entry) in importers {
+        let mut importer = Importer::default();
+        let specifiers = mapping(entry, "specifiers");
+
+        for (field, section) in SECTIONS {
+            for (name, dependency) in mapping(entry, field).into_iter().flatten() {
+                let name = match string(name) {
+                    Some(n) => n,
+                    None => continue,
+                };
+
+                // v6+ stores `{ specifier, version }`, v5 a bare version next to `specifiers`
+                let (specifier, reference) = match dependency {
+                    Value::Mapping(_) => (
+                        dependency.get("specifier").and_then(string),
+                        dependency.get("version").and_then(string),
+                    ),
+                    other => (
+                        specifiers
+                            .and_then(|s| s.get(name.as_str()))
+                            .and_then(string),
+                        string(other),
+                    ),
+                };
+
+                let target = reference.and_then(|r| resolve(&keys, version, &path, &name, &r));
+
+                importer
+                    .dependencies
+                    .entry(section)
+                    .or_default()
+                    .insert(name, LockedDependency { specifier, target });
+            }
+        }
+
+        lockfile.importers.insert(path, importer);
+    }
+
+    for (key, version) in mapping(value, "overrides").into_iter().flatten() {
+        if let (Some(key), Some(version)) = (string(key), string(version)) {
+            lockfile.overrides.insert(key, version);
+        }
+    }
+
+    lockfile
+}
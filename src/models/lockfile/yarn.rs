@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde_yaml::Value;
+
+use super::{split_name_version, LockedPackage, Lockfile, LockfileKind, Target, ROOT_IMPORTER};
+use crate::models::file;
+
+/// An entry of `yarn.lock`, shared by every descriptor resolving to it
+#[derive(Debug, Default)]
+struct Entry {
+    descriptors: Vec<String>,
+    version: String,
+
+    /// `name@npm:1.0.0` or `name@workspace:packages/a` (berry only)
+    resolution: Option<String>,
+
+    dependencies: BTreeMap<String, String>,
+}
+
+impl Entry {
+    fn name(&self) -> String {
+        let descriptor = self.resolution.as_ref().or(self.descriptors.first());
+
+        descriptor
+            .and_then(|d| split_name_version(d))
+            .map(|(name, _)| name.to_string())
+            .unwrap_or_default()
+    }
+
+    fn target(&self) -> Target {
+        let resolution = match &self.resolution {
+            Some(resolution) => resolution.clone(),
+            None => format!("{}@{}", self.name(), self.version),
+        };
+
+        match resolution.split_once("@workspace:") {
+            Some((_, ".")) => Target::Workspace(ROOT_IMPORTER.to_string()),
+            Some((_, path)) => Target::Workspace(path.to_string()),
+            None => Target::Package(resolution),
+        }
+    }
+}
+
+/// Keys a declared dependency may be locked under, yarn berry prefixes
+/// registry ranges with `npm:`
+pub fn descriptor_keys(name: &str, spec: &str) -> Vec<String> {
+    vec![
+        format!("{}@{}", name, spec),
+        format!("{}@npm:{}", name, spec),
+    ]
+}
+
+fn unquote(s: &str) -> String {
+    s.trim().trim_matches('"').to_string()
+}
+
+/// Parses the custom format of yarn v1 lockfiles
+fn parse_classic(text: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut block: Option<String> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+
+        if indent == 0 {
+            let descriptors = trimmed.trim_end_matches(':');
+
+            entries.push(Entry {
+                descriptors: descriptors.split(", ").map(unquote).collect(),
+                ..Default::default()
+            });
+
+            block = None;
+            continue;
+        }
+
+        let entry = match entries.last_mut() {
+            Some(entry) => entry,
+            None => continue,
+        };
+
+        if indent <= 2 {
+            block = None;
+
+            if let Some(field) = trimmed.strip_suffix(':') {
+                block = Some(field.to_string());
+            } else if let Some((key, value)) = trimmed.split_once(' ') {
+                if key == "version" {
+                    entry.version = unquote(value);
+                }
+            }
+
+            continue;
+        }
+
+        let is_dependencies = matches!(
+            block.as_deref(),
+            Some("dependencies" | "optionalDependencies")
+        );
+
+        if let (true, Some((name, range))) = (is_dependencies, trimmed.split_once(' ')) {
+            entry.dependencies.insert(unquote(name), unquote(range));
+        }
+    }
+
+    entries
+}
+
+/// Yarn berry lockfiles are valid YAML
+fn parse_berry(value: &Value) -> Vec<Entry> {
+    let mut entries = Vec::new();
+
+    for (key, value) in value.as_mapping().into_iter().flatten() {
+        let key = match key.as_str() {
+            Some(key) if key != "__metadata" => key,
+            _ => continue,
+        };
+
+        let field = |name: &str| value.get(name).and_then(Value::as_str).map(String::from);
+
+        let dependencies = value
+            .get("dependencies")
+            .and_then(Value::as_mapping)
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, range)| {
+                Some((name.as_str()?.to_string(), range.as_str()?.to_string()))
+            })
+            .collect();
+
+        entries.push(Entry {
+            descriptors: key.split(", ").map(unquote).collect(),
+            version: field("version").unwrap_or_default(),
+            resolution: field("resolution"),
+            dependencies,
+        });
+    }
+
+    entries
+}
+
+pub fn load(path: &Path) -> file::Result<Lockfile> {
+    let text = fs::read_to_string(path)?;
+
+    let entries = match text.contains("__metadata:") {
+        true => parse_berry(&serde_yaml::from_str(&text)?),
+        false => parse_classic(&text),
+    };
+
+    let mut lockfile = Lockfile::new(LockfileKind::Yarn, path);
+
+    for entry in entries.iter() {
+        for descriptor in entry.descriptors.iter() {
+            lockfile
+                .descriptors
+                .insert(descriptor.clone(), entry.target());
+        }
+    }
+
+    for entry in entries.iter() {
+        let id = match entry.target() {
+            Target::Package(id) => id,
+            Target::Workspace(_) => continue,
+        };
+
+        let dependencies = entry
+            .dependencies
+            .iter()
+            .filter_map(|(name, range)| {
+                let target = descriptor_keys(name, range)
+                    .iter()
+                    .find_map(|key| lockfile.descriptors.get(key))?
+                    .clone();
+
+                Some((name.clone(), target))
+            })
+            .collect();
+
+        lockfile.packages.insert(
+            id,
+            LockedPackage {
+                name: entry.name(),
+                version: entry.version.clone(),
+                dependencies,
+            },
+        );
+    }
+
+    Ok(lockfile)
+}
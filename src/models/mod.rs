@@ -1,5 +1,10 @@
 pub mod cargo_toml;
 pub mod file;
+pub mod lockfile;
+pub mod overrides;
 pub mod package_json;
+pub mod resolver;
 pub mod semantic_version;
+pub mod source_map;
+pub mod spec;
 pub mod workspace;
@@ -0,0 +1,410 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use super::semantic_version::{SemanticVersion, VersionRange};
+use crate::log;
+
+/// Where an override has been declared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverrideSource {
+    /// `pnpm.overrides` in the root manifest or `overrides` in `pnpm-workspace.yaml`
+    Pnpm,
+    /// `resolutions` in the root manifest
+    Yarn,
+    /// `overrides` in the root manifest
+    Npm,
+}
+
+impl fmt::Display for OverrideSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OverrideSource::Pnpm => "pnpm",
+            OverrideSource::Yarn => "yarn",
+            OverrideSource::Npm => "npm",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A single package in an override selector, eg: `foo@^1`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Selector {
+    pub name: String,
+    pub range: Option<String>,
+}
+
+impl Selector {
+    /// Splits `name@range` taking care of scoped packages (`@scope/name@range`)
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+        let at = s.char_indices().skip(1).find(|(_, c)| *c == '@');
+
+        match at {
+            Some((i, _)) => Self {
+                name: s[..i].to_string(),
+                range: Some(s[i + 1..].to_string()).filter(|r| !r.is_empty()),
+            },
+            None => Self {
+                name: s.to_string(),
+                range: None,
+            },
+        }
+    }
+
+    /// Checks the selector against a declared dependency spec
+    pub fn matches_spec(&self, name: &str, spec: &str) -> bool {
+        if self.name != name {
+            return false;
+        }
+
+        let range = match &self.range {
+            Some(range) => range,
+            None => return true,
+        };
+
+        if range == spec {
+            return true;
+        }
+
+        let declared = VersionRange::from_str(spec)
+            .ok()
+            .and_then(|r| r.min_version());
+
+        match (VersionRange::from_str(range), declared) {
+            (Ok(range), Some(version)) => range.satisfies(&version),
+            _ => false,
+        }
+    }
+
+    /// Checks the selector against an installed version
+    pub fn matches_version(&self, name: &str, version: &SemanticVersion) -> bool {
+        if self.name != name {
+            return false;
+        }
+
+        match &self.range {
+            Some(range) => VersionRange::from_str(range)
+                .map(|r| r.satisfies(version))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.range {
+            Some(range) => write!(f, "{}@{}", self.name, range),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// A forced version for a package, optionally scoped to a dependency path
+#[derive(Debug, Clone, Serialize)]
+pub struct Override {
+    pub source: OverrideSource,
+
+    /// The selector as written in the manifest
+    pub key: String,
+
+    /// Parent packages followed by the overridden package
+    pub path: Vec<Selector>,
+
+    pub version: String,
+}
+
+impl Override {
+    /// Builds an override, `None` (with a warning) when a selector of the key
+    /// has no package name, eg: `**` or an empty key
+    fn new(
+        source: OverrideSource,
+        key: &str,
+        path: Vec<Selector>,
+        version: String,
+    ) -> Option<Self> {
+        if path.is_empty() || path.iter().any(|s| s.name.is_empty()) {
+            log::warn(&format!(
+                "Ignoring the {} override `{}`: no package name",
+                source, key
+            ));
+            return None;
+        }
+
+        Some(Self {
+            source,
+            key: key.to_string(),
+            path,
+            version,
+        })
+    }
+
+    /// The overridden package (last element of the path)
+    pub fn target(&self) -> Option<&Selector> {
+        self.path.last()
+    }
+
+    pub fn parents(&self) -> &[Selector] {
+        match self.path.split_last() {
+            Some((_, parents)) => parents,
+            None => &[],
+        }
+    }
+
+    /// Whether the override replaces a direct dependency of `workspace`
+    pub fn applies_to(&self, workspace: &str, name: &str, spec: &str) -> bool {
+        let parents_match = match self.parents() {
+            [] => true,
+            [parent] => parent.name == workspace,
+            _ => false,
+        };
+
+        parents_match
+            && self
+                .target()
+                .map(|t| t.matches_spec(name, spec))
+                .unwrap_or(false)
+    }
+
+    /// Whether the override removes the dependency instead of replacing it (pnpm only)
+    pub fn is_removal(&self) -> bool {
+        self.source == OverrideSource::Pnpm && self.version == "-"
+    }
+}
+
+/// Resolves a `$name` version, which references the spec of a direct
+/// dependency of the root manifest, other versions are returned as is
+fn resolve_reference(version: &str, root: &HashMap<String, String>) -> String {
+    match version.strip_prefix('$') {
+        Some(reference) => root.get(reference).cloned().unwrap_or(version.to_string()),
+        None => version.to_string(),
+    }
+}
+
+/// Parses pnpm overrides (`foo`, `foo@1`, `foo>bar`, `foo@1>bar@2`), `root`
+/// holds the direct dependencies of the root manifest `$name` versions refer to.
+/// The overrides are sorted by key.
+pub fn parse_pnpm(map: &HashMap<String, String>, root: &HashMap<String, String>) -> Vec<Override> {
+    let mut overrides: Vec<Override> = map
+        .iter()
+        .filter_map(|(key, version)| {
+            Override::new(
+                OverrideSource::Pnpm,
+                key,
+                key.split('>').map(Selector::parse).collect(),
+                resolve_reference(version, root),
+            )
+        })
+        .collect();
+
+    overrides.sort_by(|a, b| a.key.cmp(&b.key));
+    overrides
+}
+
+/// Parses yarn resolutions (`foo`, `**/foo`, `foo/bar`, `@scope/foo/**/bar`),
+/// sorted by key
+pub fn parse_yarn(map: &HashMap<String, String>) -> Vec<Override> {
+    let mut overrides: Vec<Override> = map
+        .iter()
+        .filter_map(|(key, version)| {
+            let mut path: Vec<String> = Vec::new();
+            let mut segments = key.split('/').filter(|s| !s.is_empty() && *s != "**");
+
+            while let Some(segment) = segments.next() {
+                match segment.starts_with('@') {
+                    true => path.push(format!(
+                        "{}/{}",
+                        segment,
+                        segments.next().unwrap_or_default()
+                    )),
+                    false => path.push(segment.to_string()),
+                }
+            }
+
+            Override::new(
+                OverrideSource::Yarn,
+                key,
+                path.iter().map(|s| Selector::parse(s)).collect(),
+                version.clone(),
+            )
+        })
+        .collect();
+
+    overrides.sort_by(|a, b| a.key.cmp(&b.key));
+    overrides
+}
+
+/// Parses npm overrides, which are nested objects where `.` refers to the
+/// enclosing package and `$name` references the root dependency version.
+pub fn parse_npm(value: &serde_json::Value, root: &HashMap<String, String>) -> Vec<Override> {
+    let mut overrides = Vec::new();
+    collect_npm(value, &[], root, &mut overrides);
+    overrides
+}
+
+fn collect_npm(
+    value: &serde_json::Value,
+    parents: &[Selector],
+    root: &HashMap<String, String>,
+    overrides: &mut Vec<Override>,
+) {
+    let object = match value.as_object() {
+        Some(o) => o,
+        None => return,
+    };
+
+    for (key, value) in object {
+        if key == "." {
+            continue;
+        }
+
+        let mut path = parents.to_vec();
+        path.push(Selector::parse(key));
+
+        let version = match value {
+            serde_json::Value::String(v) => Some(v.as_str()),
+            serde_json::Value::Object(o) => o.get(".").and_then(|v| v.as_str()),
+            _ => None,
+        };
+
+        if let Some(version) = version {
+            let key = path
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(">");
+
+            overrides.extend(Override::new(
+                OverrideSource::Npm,
+                &key,
+                path.clone(),
+                resolve_reference(version, root),
+            ));
+        }
+
+        if value.is_object() {
+            collect_npm(value, &path, root, overrides);
+        }
+    }
+}
+
+/// Returns the version that actually gets installed for a direct dependency
+/// (`None` when an override removes the dependency).
+///
+/// The most specific override wins: the one with the longest path, then the
+/// one whose package has a version range (`foo@^1` over `foo`), then the
+/// first source (pnpm, yarn, npm) and the first key.
+pub fn effective_version(
+    overrides: &[Override],
+    workspace: &str,
+    name: &str,
+    spec: &str,
+) -> Option<String> {
+    let matched = overrides
+        .iter()
+        .filter(|o| o.applies_to(workspace, name, spec))
+        .min_by_key(|o| {
+            let unranged = o.target().map(|t| t.range.is_none());
+            (Reverse(o.path.len()), unranged, o.source, &o.key)
+        });
+
+    match matched {
+        Some(o) if o.is_removal() => None,
+        Some(o) => Some(o.version.clone()),
+        None => Some(spec.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn keys_without_package_are_skipped() {
+        let yarn = parse_yarn(&map(&[("**", "1.0.0"), ("", "1.0.0"), ("**/foo", "2.0.0")]));
+        assert_eq!(yarn.len(), 1);
+        assert_eq!(yarn[0].target().unwrap().name, "foo");
+        assert!(yarn[0].parents().is_empty());
+
+        let pnpm = parse_pnpm(&map(&[("", "1.0.0"), ("foo>", "1.0.0")]), &HashMap::new());
+        assert!(pnpm.is_empty());
+    }
+
+    #[test]
+    fn scoped_paths() {
+        let yarn = parse_yarn(&map(&[("@scope/a/**/b@^1", "1.2.0")]));
+        let names: Vec<_> = yarn[0].path.iter().map(|s| s.name.as_str()).collect();
+
+        assert_eq!(names, vec!["@scope/a", "b"]);
+        assert!(yarn[0].applies_to("@scope/a", "b", "^1.0.0"));
+        assert!(!yarn[0].applies_to("c", "b", "^1.0.0"));
+    }
+
+    #[test]
+    fn pnpm_references_resolve_to_the_root_spec() {
+        let root = map(&[("react", "^18.2.0")]);
+        let pnpm = parse_pnpm(&map(&[("react", "$react"), ("vue", "$vue")]), &root);
+        let version = |name: &str| {
+            pnpm.iter()
+                .find(|o| o.key == name)
+                .map(|o| o.version.clone())
+        };
+
+        assert_eq!(version("react").as_deref(), Some("^18.2.0"));
+        assert_eq!(version("vue").as_deref(), Some("$vue"));
+    }
+
+    #[test]
+    fn npm_references_and_nesting() {
+        let value = serde_json::json!({
+            "foo": { ".": "$foo", "bar": "1.0.0" },
+            "": "2.0.0"
+        });
+        let npm = parse_npm(&value, &map(&[("foo", "^3.0.0")]));
+        let keys: Vec<_> = npm
+            .iter()
+            .map(|o| (o.key.as_str(), o.version.as_str()))
+            .collect();
+
+        assert_eq!(keys, vec![("foo", "^3.0.0"), ("foo>bar", "1.0.0")]);
+    }
+
+    #[test]
+    fn ranged_overrides_take_precedence_over_bare_names() {
+        for _ in 0..8 {
+            let overrides = parse_pnpm(
+                &map(&[("foo", "2.0.0"), ("foo@^1", "1.5.0"), ("a>foo", "1.9.0")]),
+                &HashMap::new(),
+            );
+
+            let keys: Vec<&str> = overrides.iter().map(|o| o.key.as_str()).collect();
+            assert_eq!(keys, ["a>foo", "foo", "foo@^1"]);
+
+            assert_eq!(
+                effective_version(&overrides, "b", "foo", "^1.0.0"),
+                Some("1.5.0".to_string())
+            );
+            assert_eq!(
+                effective_version(&overrides, "b", "foo", "^3.0.0"),
+                Some("2.0.0".to_string())
+            );
+            assert_eq!(
+                effective_version(&overrides, "a", "foo", "^1.0.0"),
+                Some("1.9.0".to_string())
+            );
+        }
+    }
+}
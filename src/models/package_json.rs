@@ -1,14 +1,17 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::models::file::File;
-use serde::Deserialize;
+use crate::models::file::{self, File};
+use crate::models::overrides::{self, Override};
+use crate::models::source_map::{Location, SourceMap};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Clone)]
-#[derive(Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Dependencies(pub HashMap<String, String>);
 
-
-
 impl Dependencies {
     pub fn len(&self) -> usize {
         self.0.len()
@@ -19,9 +22,46 @@ impl Dependencies {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// A dependency section of the manifest
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum Section {
+    Dependencies,
+    DevDependencies,
+    PeerDependencies,
+}
+
+impl Section {
+    /// The key of the section in `package.json`
+    pub fn key(&self) -> &'static str {
+        match self {
+            Section::Dependencies => "dependencies",
+            Section::DevDependencies => "devDependencies",
+            Section::PeerDependencies => "peerDependencies",
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PackageJson {
+    /// Location of the manifest on disk
+    #[serde(skip)]
+    pub path: PathBuf,
+
+    /// Position of the dependency entries in the manifest
+    #[serde(skip)]
+    pub source_map: SourceMap,
+
     pub name: String,
+    pub version: Option<String>,
     pub workspaces: Option<Vec<String>>,
 
     #[serde(default)]
@@ -32,13 +72,83 @@ pub struct PackageJson {
 
     #[serde(rename = "peerDependencies", default)]
     pub peer_dependencies: Dependencies,
+
+    #[serde(default)]
+    pub pnpm: PnpmConfig,
+
+    /// yarn `resolutions`
+    #[serde(default)]
+    pub resolutions: HashMap<String, String>,
+
+    /// npm `overrides`
+    #[serde(default)]
+    pub overrides: serde_json::Value,
 }
 
-impl File<PackageJson> for PackageJson {}
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PnpmConfig {
+    #[serde(default)]
+    pub overrides: HashMap<String, String>,
+}
+
+impl File<PackageJson> for PackageJson {
+    /// Reads the manifest keeping track of where each dependency is declared
+    fn load(path: &Path) -> file::Result<PackageJson> {
+        let text = fs::read_to_string(path)?;
+        PackageJson::parse(path, &text)
+    }
+}
 impl PackageJson {
+    /// Parses a manifest read from somewhere else than the working tree (eg: git)
+    pub fn parse(path: &Path, text: &str) -> file::Result<PackageJson> {
+        let mut manifest: PackageJson = serde_json::from_str(text)?;
+        manifest.path = path.to_path_buf();
+        manifest.source_map = SourceMap::parse(text);
+
+        Ok(manifest)
+    }
+
     pub fn has_workspaces(self) -> bool {
         self.workspaces.unwrap().is_empty()
     }
+
+    pub fn section(&self, section: Section) -> &Dependencies {
+        match section {
+            Section::Dependencies => &self.dependencies,
+            Section::DevDependencies => &self.dev_dependencies,
+            Section::PeerDependencies => &self.peer_dependencies,
+        }
+    }
+
+    /// Where a dependency is declared in the manifest
+    pub fn locate(&self, section: Section, name: &str) -> Option<Location> {
+        self.source_map
+            .get(section.key(), name)
+            .map(|entry| Location {
+                path: self.path.to_string_lossy().replace('\\', "/"),
+                line: entry.line,
+                column: entry.column,
+            })
+    }
+
+    /// Collects pnpm overrides, yarn resolutions and npm overrides
+    pub fn get_overrides(&self) -> Vec<Override> {
+        let root = self.direct_dependencies();
+
+        let mut result = overrides::parse_pnpm(&self.pnpm.overrides, &root);
+        result.extend(overrides::parse_yarn(&self.resolutions));
+        result.extend(overrides::parse_npm(&self.overrides, &root));
+
+        result
+    }
+
+    /// Specs of the dependencies and devDependencies, which `$name` override
+    /// versions may refer to
+    pub fn direct_dependencies(&self) -> HashMap<String, String> {
+        let mut result = self.dev_dependencies.0.clone();
+        result.extend(self.dependencies.0.clone());
+        result
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -82,10 +192,7 @@ pub mod utils {
     pub fn list_dirs(path: &Path) -> Vec<PathBuf> {
         WalkDir::new(path)
             .into_iter()
-            .filter_map(move |p| match p {
-                Ok(entry) => Some(entry),
-                _ => None,
-            })
+            .filter_map(|p| p.ok())
             .filter(|p| p.path().is_dir())
             .map(|f| PathBuf::from(f.path()))
             .collect()
@@ -94,10 +201,7 @@ pub mod utils {
     pub fn list_files(path: &Path) -> Vec<PathBuf> {
         WalkDir::new(path)
             .into_iter()
-            .filter_map(move |p| match p {
-                Ok(entry) => Some(entry),
-                _ => None,
-            })
+            .filter_map(|p| p.ok())
             .filter(|p| p.path().is_file())
             .map(|f| PathBuf::from(f.path()))
             .collect()
@@ -0,0 +1,43 @@
+use super::overrides::{self, Override};
+use super::spec::Spec;
+use super::workspace::Workspace;
+
+/// Computes the spec that actually gets installed for a declared dependency
+/// by resolving pnpm catalogs and applying root overrides.
+#[derive(Debug, Clone, Default)]
+pub struct Resolver {
+    pub overrides: Vec<Override>,
+    pub workspace: Option<Workspace>,
+}
+
+impl Resolver {
+    pub fn new(overrides: Vec<Override>, workspace: Option<Workspace>) -> Self {
+        Self {
+            overrides,
+            workspace,
+        }
+    }
+
+    /// Returns `None` when an override removes the dependency
+    pub fn resolve(&self, workspace: &str, name: &str, spec: &str) -> Option<String> {
+        let spec = self
+            .workspace
+            .as_ref()
+            .and_then(|w| w.resolve_catalog(name, spec))
+            .map(String::as_str)
+            .unwrap_or(spec);
+
+        // overrides target the real package behind an alias
+        if let Some((real, inner)) = Spec::parse(spec).dealias() {
+            return overrides::effective_version(
+                &self.overrides,
+                workspace,
+                real,
+                &inner.to_string(),
+            )
+            .map(|version| format!("npm:{}@{}", real, version));
+        }
+
+        overrides::effective_version(&self.overrides, workspace, name, spec)
+    }
+}
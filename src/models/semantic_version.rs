@@ -1,12 +1,57 @@
+use std::cmp::Ordering;
+use std::fmt;
 use std::str::FromStr;
 
-#[derive(Default)]
+#[derive(Default, Debug, Clone)]
 pub struct SemanticVersion {
     workspace: bool,
 
     pub major: usize,
     pub minor: usize,
     pub patch: usize,
+
+    /// Dot separated pre-release identifiers (`1.0.0-beta.1` => `["beta", "1"]`)
+    pub pre: Vec<String>,
+}
+
+impl SemanticVersion {
+    pub fn new(major: usize, minor: usize, patch: usize) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_workspace(&self) -> bool {
+        self.workspace
+    }
+
+    fn cmp_pre(&self, other: &Self) -> Ordering {
+        // a version without pre-release identifiers has higher precedence
+        match (self.pre.is_empty(), other.pre.is_empty()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            _ => {}
+        }
+
+        for (a, b) in self.pre.iter().zip(other.pre.iter()) {
+            let ord = match (a.parse::<usize>(), b.parse::<usize>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            };
+
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+
+        self.pre.len().cmp(&other.pre.len())
+    }
 }
 
 impl From<String> for SemanticVersion {
@@ -17,23 +62,25 @@ impl From<String> for SemanticVersion {
 
 impl PartialEq for SemanticVersion {
     fn eq(&self, other: &Self) -> bool {
-        self.major == other.major && self.minor == other.minor && self.patch == other.patch
+        self.cmp(other) == Ordering::Equal
     }
 }
 
-impl PartialOrd for SemanticVersion {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match self.major.partial_cmp(&other.major) {
-            Some(core::cmp::Ordering::Equal) => {}
-            ord => return ord,
-        }
+impl Eq for SemanticVersion {}
 
-        match self.minor.partial_cmp(&other.minor) {
-            Some(core::cmp::Ordering::Equal) => {}
-            ord => return ord,
-        }
+impl PartialOrd for SemanticVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-        self.patch.partial_cmp(&other.patch)
+impl Ord for SemanticVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.major
+            .cmp(&other.major)
+            .then(self.minor.cmp(&other.minor))
+            .then(self.patch.cmp(&other.patch))
+            .then_with(|| self.cmp_pre(other))
     }
 }
 
@@ -53,6 +100,16 @@ impl FromStr for SemanticVersion {
                     return Self::from_str(stripped);
                 }
 
+                let s = s.trim().trim_start_matches(['=', 'v']);
+
+                // build metadata never affects precedence
+                let s = s.split('+').next().unwrap_or_default();
+
+                let (s, pre) = match s.split_once('-') {
+                    Some((version, pre)) => (version, pre),
+                    None => (s, ""),
+                };
+
                 let parts: Vec<&str> = s.split('.').collect();
 
                 if !parts.is_empty() {
@@ -67,18 +124,432 @@ impl FromStr for SemanticVersion {
                     v.patch = parts[2].parse().unwrap_or_default();
                 }
 
+                if !pre.is_empty() {
+                    v.pre = pre.split('.').map(String::from).collect();
+                }
+
                 Ok(v)
             }
         }
     }
 }
 
-impl ToString for SemanticVersion {
-    fn to_string(&self) -> String {
+impl fmt::Display for SemanticVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.workspace {
-            return "workspace".to_string();
+            return write!(f, "workspace");
         }
 
-        format!("{}.{}.{}", self.major, self.minor, self.patch)
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+
+        if !self.pre.is_empty() {
+            write!(f, "-{}", self.pre.join("."))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+#[derive(Debug, Clone)]
+struct Comparator {
+    operator: Operator,
+    version: SemanticVersion,
+}
+
+impl Comparator {
+    fn new(operator: Operator, version: SemanticVersion) -> Self {
+        Self { operator, version }
+    }
+
+    /// Whether the comparator opts in to the pre-releases of `v`
+    fn allows_pre(&self, v: &SemanticVersion) -> bool {
+        !self.version.pre.is_empty()
+            && (self.version.major, self.version.minor, self.version.patch)
+                == (v.major, v.minor, v.patch)
+    }
+
+    fn matches(&self, v: &SemanticVersion) -> bool {
+        match self.operator {
+            Operator::Eq => v == &self.version,
+            Operator::Lt => v < &self.version,
+            Operator::Lte => v <= &self.version,
+            Operator::Gt => v > &self.version,
+            Operator::Gte => v >= &self.version,
+        }
+    }
+}
+
+/// A version that may omit trailing components (`1`, `1.2`, `1.x`, `*`)
+#[derive(Debug, Clone, Default)]
+struct Partial {
+    major: Option<usize>,
+    minor: Option<usize>,
+    patch: Option<usize>,
+    pre: Vec<String>,
+}
+
+impl Partial {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().trim_start_matches(['=', 'v']);
+        let s = s.split('+').next().unwrap_or_default();
+
+        let (s, pre) = match s.split_once('-') {
+            Some((version, pre)) => (version, pre),
+            None => (s, ""),
+        };
+
+        let mut parts = s.split('.');
+        let mut component = || -> Option<Option<usize>> {
+            match parts.next() {
+                None | Some("x" | "X" | "*" | "") => Some(None),
+                Some(n) => n.parse().ok().map(Some),
+            }
+        };
+
+        let mut partial = Partial {
+            major: component()?,
+            minor: component()?,
+            patch: component()?,
+            ..Default::default()
+        };
+
+        // once a component is a wildcard every following component is too
+        if partial.major.is_none() {
+            partial.minor = None;
+        }
+
+        if partial.minor.is_none() {
+            partial.patch = None;
+        }
+
+        if partial.patch.is_some() && !pre.is_empty() {
+            partial.pre = pre.split('.').map(String::from).collect();
+        }
+
+        Some(partial)
+    }
+
+    fn floor(&self) -> SemanticVersion {
+        SemanticVersion {
+            major: self.major.unwrap_or_default(),
+            minor: self.minor.unwrap_or_default(),
+            patch: self.patch.unwrap_or_default(),
+            pre: self.pre.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// First version *not* covered by this partial (`1.2` => `1.3.0`)
+    fn ceil(&self) -> Option<SemanticVersion> {
+        match (self.major, self.minor, self.patch) {
+            (None, _, _) => None,
+            (Some(major), None, _) => Some(SemanticVersion::new(major + 1, 0, 0)),
+            (Some(major), Some(minor), None) => Some(SemanticVersion::new(major, minor + 1, 0)),
+            (Some(major), Some(minor), Some(patch)) => {
+                Some(SemanticVersion::new(major, minor, patch + 1))
+            }
+        }
+    }
+
+    fn is_exact(&self) -> bool {
+        self.patch.is_some()
+    }
+}
+
+/// An npm style version range, eg: `^1.2.3 || >=2.0.0 <3`
+#[derive(Debug, Clone)]
+pub struct VersionRange {
+    raw: String,
+    sets: Vec<Vec<Comparator>>,
+}
+
+impl VersionRange {
+    /// Checks whether the given version satisfies the range.
+    ///
+    /// Like npm, a pre-release only satisfies a set of comparators when one
+    /// of them has a pre-release of the same `major.minor.patch`: `>=18`
+    /// does not match `19.0.0-rc.1`, `>=19.0.0-rc.0` does.
+    pub fn satisfies(&self, version: &SemanticVersion) -> bool {
+        self.sets.iter().any(|set| {
+            set.iter().all(|c| c.matches(version))
+                && (version.pre.is_empty() || set.iter().any(|c| c.allows_pre(version)))
+        })
+    }
+
+    /// Lowest version matching the range, if any
+    pub fn min_version(&self) -> Option<SemanticVersion> {
+        self.sets
+            .iter()
+            .filter_map(|set| {
+                let mut candidate = SemanticVersion::default();
+
+                for c in set {
+                    let lower = match c.operator {
+                        Operator::Eq | Operator::Gte => c.version.clone(),
+                        Operator::Gt if c.version.pre.is_empty() => SemanticVersion::new(
+                            c.version.major,
+                            c.version.minor,
+                            c.version.patch + 1,
+                        ),
+                        Operator::Gt => {
+                            let mut v = c.version.clone();
+                            v.pre.push("0".into());
+                            v
+                        }
+                        Operator::Lt | Operator::Lte => continue,
+                    };
+
+                    if lower > candidate {
+                        candidate = lower;
+                    }
+                }
+
+                set.iter()
+                    .all(|c| c.matches(&candidate))
+                    .then_some(candidate)
+            })
+            .min()
+    }
+
+    /// Lowest version matching the range, `None` when it has no lower bound
+    /// (`*`, `<2`)
+    pub fn lower_bound(&self) -> Option<SemanticVersion> {
+        let bounded = self.sets.iter().all(|set| {
+            set.iter()
+                .any(|c| matches!(c.operator, Operator::Eq | Operator::Gte | Operator::Gt))
+        });
+
+        match bounded {
+            true => self.min_version(),
+            false => None,
+        }
+    }
+
+    fn parse_set(s: &str) -> Option<Vec<Comparator>> {
+        let s = s.trim();
+
+        if let Some((from, to)) = s.split_once(" - ") {
+            let from = Partial::parse(from)?;
+            let to = Partial::parse(to)?;
+
+            let mut set = vec![Comparator::new(Operator::Gte, from.floor())];
+
+            if to.is_exact() {
+                set.push(Comparator::new(Operator::Lte, to.floor()));
+            } else if let Some(ceil) = to.ceil() {
+                set.push(Comparator::new(Operator::Lt, ceil));
+            }
+
+            return Some(set);
+        }
+
+        let mut set = Vec::new();
+        let mut tokens = s.split_whitespace().peekable();
+
+        while let Some(token) = tokens.next() {
+            // allow a space between the operator and the version (`>= 1.2.3`)
+            let token = if token.chars().all(|c| "<>=~^".contains(c)) {
+                format!("{}{}", token, tokens.next()?)
+            } else {
+                token.to_string()
+            };
+
+            set.extend(Self::parse_comparator(&token)?);
+        }
+
+        Some(set)
+    }
+
+    fn parse_comparator(token: &str) -> Option<Vec<Comparator>> {
+        let operators = [">=", "<=", ">", "<", "=", "~>", "~", "^"];
+        let (op, rest) = operators
+            .iter()
+            .find_map(|op| token.strip_prefix(op).map(|rest| (*op, rest)))
+            .unwrap_or(("", token));
+
+        let partial = Partial::parse(rest)?;
+        let floor = partial.floor();
+
+        let comparators = match op {
+            ">=" => vec![Comparator::new(Operator::Gte, floor)],
+            "<=" => match partial.is_exact() {
+                true => vec![Comparator::new(Operator::Lte, floor)],
+                false => partial
+                    .ceil()
+                    .map(|c| vec![Comparator::new(Operator::Lt, c)])
+                    .unwrap_or_default(),
+            },
+            ">" => match partial.is_exact() {
+                true => vec![Comparator::new(Operator::Gt, floor)],
+                false => match partial.ceil() {
+                    Some(ceil) => vec![Comparator::new(Operator::Gte, ceil)],
+                    // `>*` can never be satisfied
+                    None => vec![Comparator::new(Operator::Lt, SemanticVersion::default())],
+                },
+            },
+            "<" => vec![Comparator::new(Operator::Lt, floor)],
+            "~" | "~>" => {
+                let ceil = match (partial.major, partial.minor) {
+                    (Some(major), Some(minor)) => Some(SemanticVersion::new(major, minor + 1, 0)),
+                    _ => partial.ceil(),
+                };
+
+                let mut set = vec![Comparator::new(Operator::Gte, floor)];
+                set.extend(ceil.map(|c| Comparator::new(Operator::Lt, c)));
+                set
+            }
+            "^" => {
+                let ceil = match (partial.major, partial.minor, partial.patch) {
+                    (None, _, _) => None,
+                    (Some(0), Some(0), Some(patch)) => Some(SemanticVersion::new(0, 0, patch + 1)),
+                    (Some(0), Some(minor), _) => Some(SemanticVersion::new(0, minor + 1, 0)),
+                    (Some(major), _, _) => Some(SemanticVersion::new(major + 1, 0, 0)),
+                };
+
+                let mut set = vec![Comparator::new(Operator::Gte, floor)];
+                set.extend(ceil.map(|c| Comparator::new(Operator::Lt, c)));
+                set
+            }
+            _ => match (partial.is_exact(), partial.ceil()) {
+                (true, _) => vec![Comparator::new(Operator::Eq, floor)],
+                (false, Some(ceil)) => vec![
+                    Comparator::new(Operator::Gte, floor),
+                    Comparator::new(Operator::Lt, ceil),
+                ],
+                (false, None) => vec![],
+            },
+        };
+
+        Some(comparators)
+    }
+}
+
+impl FromStr for VersionRange {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let sets = s
+            .split("||")
+            .map(Self::parse_set)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(())?;
+
+        Ok(Self {
+            raw: s.trim().to_string(),
+            sets,
+        })
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn satisfies(range: &str, version: &str) -> bool {
+        let range = VersionRange::from_str(range).unwrap();
+        range.satisfies(&SemanticVersion::from_str(version).unwrap())
+    }
+
+    fn min(range: &str) -> Option<String> {
+        let range = VersionRange::from_str(range).unwrap();
+        range.min_version().map(|v| v.to_string())
+    }
+
+    #[test]
+    fn caret() {
+        assert!(satisfies("^1.2.3", "1.2.3"));
+        assert!(satisfies("^1.2.3", "1.9.0"));
+        assert!(!satisfies("^1.2.3", "2.0.0"));
+        assert!(!satisfies("^1.2.3", "1.2.2"));
+        assert!(satisfies("^0.2.3", "0.2.9"));
+        assert!(!satisfies("^0.2.3", "0.3.0"));
+        assert!(satisfies("^0.0.3", "0.0.3"));
+        assert!(!satisfies("^0.0.3", "0.0.4"));
+        assert_eq!(min("^1.2.3").as_deref(), Some("1.2.3"));
+    }
+
+    #[test]
+    fn tilde() {
+        assert!(satisfies("~1.2.3", "1.2.9"));
+        assert!(!satisfies("~1.2.3", "1.3.0"));
+        assert!(satisfies("~1", "1.9.9"));
+        assert!(!satisfies("~1", "2.0.0"));
+        assert!(satisfies("~>1.2", "1.2.5"));
+        assert_eq!(min("~4.17.1").as_deref(), Some("4.17.1"));
+    }
+
+    #[test]
+    fn hyphen() {
+        assert!(satisfies("1.2.3 - 2.3.4", "1.2.3"));
+        assert!(satisfies("1.2.3 - 2.3.4", "2.3.4"));
+        assert!(!satisfies("1.2.3 - 2.3.4", "2.3.5"));
+        assert!(satisfies("1.2 - 2.3", "2.3.9"));
+        assert!(!satisfies("1.2 - 2.3", "2.4.0"));
+        assert!(!satisfies("1.2 - 2.3", "1.1.9"));
+    }
+
+    #[test]
+    fn or() {
+        assert!(satisfies("^1.0.0 || ^3.0.0", "1.5.0"));
+        assert!(satisfies("^1.0.0 || ^3.0.0", "3.1.0"));
+        assert!(!satisfies("^1.0.0 || ^3.0.0", "2.0.0"));
+        assert_eq!(min("^3.0.0 || ^1.0.0").as_deref(), Some("1.0.0"));
+    }
+
+    #[test]
+    fn x_ranges() {
+        assert!(satisfies("*", "0.0.1"));
+        assert!(satisfies("", "9.9.9"));
+        assert!(satisfies("1.x", "1.9.0"));
+        assert!(!satisfies("1.x", "2.0.0"));
+        assert!(satisfies("1.2.X", "1.2.7"));
+        assert!(!satisfies("1.2.*", "1.3.0"));
+    }
+
+    #[test]
+    fn partials_and_comparators() {
+        assert!(satisfies("1", "1.4.0"));
+        assert!(!satisfies("1", "2.0.0"));
+        assert!(satisfies("1.2", "1.2.8"));
+        assert!(satisfies(">=1 <2", "1.9.9"));
+        assert!(!satisfies(">=1 <2", "2.0.0"));
+        assert!(satisfies(">= 1.2.3", "1.2.3"));
+        assert!(!satisfies(">1.2", "1.2.9"));
+        assert!(satisfies(">1.2", "1.3.0"));
+        assert!(satisfies("<=1.2", "1.2.9"));
+        assert!(satisfies("=1.2.3", "1.2.3"));
+        assert!(satisfies("v1.2.3", "1.2.3"));
+        assert_eq!(min(">=1 <2").as_deref(), Some("1.0.0"));
+        assert_eq!(min(">1.2.3").as_deref(), Some("1.2.4"));
+        assert_eq!(min("<2"), Some("0.0.0".to_string()));
+        assert!(VersionRange::from_str("not a range").is_err());
+    }
+
+    #[test]
+    fn prereleases() {
+        assert!(!satisfies(">=18", "19.0.0-rc.1"));
+        assert!(!satisfies(">=4 <5", "5.0.0-beta.1"));
+        assert!(!satisfies("^1.2.3", "1.3.0-beta"));
+        assert!(satisfies("^1.2.3-beta.1", "1.2.3-beta.2"));
+        assert!(satisfies("^1.2.3-beta.1", "1.2.4"));
+        assert!(!satisfies("^1.2.3-beta.1", "1.2.4-beta"));
+        assert!(!satisfies("^1.2.3-beta.2", "1.2.3-beta.1"));
+        assert!(satisfies(">=19.0.0-rc.0", "19.0.0-rc.1"));
+        assert!(satisfies("1.0.0-alpha - 1.0.0", "1.0.0-beta"));
     }
 }
@@ -0,0 +1,173 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+/// Position of a `"name": "spec"` entry nested in a top level object of a
+/// JSON document, eg: a dependency inside `devDependencies`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub section: String,
+    pub name: String,
+
+    /// 1-based line of the entry key
+    pub line: usize,
+
+    /// 1-based column of the entry key
+    pub column: usize,
+
+    /// Byte range of the value, quotes included
+    pub value: Range<usize>,
+}
+
+/// A position in a file
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Location {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Maps second level string entries of a JSON document to their position,
+/// which `serde_json` discards while deserializing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceMap {
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+enum Token {
+    Str(String, Range<usize>),
+    Colon,
+    Comma,
+    Open(char),
+    Close,
+    Literal,
+}
+
+enum Container {
+    Array,
+    Object {
+        key: Option<String>,
+        current: Option<(String, usize)>,
+    },
+}
+
+impl SourceMap {
+    pub fn parse(text: &str) -> Self {
+        let mut entries = Vec::new();
+        let mut stack: Vec<Container> = Vec::new();
+
+        for token in tokenize(text) {
+            match token {
+                Token::Open(c) => {
+                    // the key owning the new container, if any
+                    let key = match stack.last_mut() {
+                        Some(Container::Object { current, .. }) => current.take().map(|(k, _)| k),
+                        _ => None,
+                    };
+
+                    stack.push(match c {
+                        '{' => Container::Object { key, current: None },
+                        _ => Container::Array,
+                    });
+                }
+                Token::Close => {
+                    stack.pop();
+                }
+                Token::Literal => {
+                    if let Some(Container::Object { current, .. }) = stack.last_mut() {
+                        *current = None;
+                    }
+                }
+                Token::Colon | Token::Comma => {}
+                Token::Str(s, span) => {
+                    let depth = stack.len();
+
+                    let (current, key) = match stack.last_mut() {
+                        Some(Container::Object { current, key }) => (current, key),
+                        _ => continue,
+                    };
+
+                    match current.take() {
+                        None => *current = Some((s, span.start)),
+                        Some((name, start)) if depth == 2 => {
+                            let (line, column) = position(text, start);
+
+                            entries.push(Entry {
+                                section: key.clone().unwrap_or_default(),
+                                name,
+                                line,
+                                column,
+                                value: span,
+                            });
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+
+        Self { entries }
+    }
+
+    pub fn get(&self, section: &str, name: &str) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|e| e.section == section && e.name == name)
+    }
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let start = i;
+                i += 1;
+
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+
+                    i += 1;
+                }
+
+                let end = (i + 1).min(bytes.len());
+                let raw = &text[start..end];
+                let value = serde_json::from_str(raw).unwrap_or_else(|_| raw.to_string());
+
+                tokens.push(Token::Str(value, start..end));
+            }
+            b'{' => tokens.push(Token::Open('{')),
+            b'[' => tokens.push(Token::Open('[')),
+            b'}' | b']' => tokens.push(Token::Close),
+            b':' => tokens.push(Token::Colon),
+            b',' => tokens.push(Token::Comma),
+            c if c.is_ascii_whitespace() => {}
+            _ => {
+                while i + 1 < bytes.len() && !b",:{}[]\" \t\r\n".contains(&bytes[i + 1]) {
+                    i += 1;
+                }
+
+                tokens.push(Token::Literal);
+            }
+        }
+
+        i += 1;
+    }
+
+    tokens
+}
+
+/// 1-based line and column of a byte offset
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+
+    (line, column)
+}
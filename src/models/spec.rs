@@ -0,0 +1,496 @@
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use super::semantic_version::VersionRange;
+use super::workspace::CATALOG_PROTOCOL;
+
+/// The kind of a dependency spec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SpecKind {
+    Semver,
+    Tag,
+    Workspace,
+    Catalog,
+    Alias,
+    File,
+    Link,
+    Portal,
+    Git,
+    Tarball,
+    Unknown,
+}
+
+impl fmt::Display for SpecKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SpecKind::Semver => "semver",
+            SpecKind::Tag => "tag",
+            SpecKind::Workspace => "workspace",
+            SpecKind::Catalog => "catalog",
+            SpecKind::Alias => "alias",
+            SpecKind::File => "file",
+            SpecKind::Link => "link",
+            SpecKind::Portal => "portal",
+            SpecKind::Git => "git",
+            SpecKind::Tarball => "tarball",
+            SpecKind::Unknown => "unknown",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Where a non-registry dependency comes from and which revision of it is used
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct Source {
+    /// Normalized repository url, tarball url or path
+    pub location: String,
+
+    /// Commit, branch, tag or tarball version
+    pub reference: Option<String>,
+}
+
+/// A parsed dependency spec, the value of an entry in `dependencies`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Spec {
+    /// `^1.2.3`, `>=1 <2`, `1.x`, `*`
+    Semver(String),
+    /// A dist tag: `latest`, `next`
+    Tag(String),
+    /// `workspace:*`, `workspace:^`, `workspace:~`, `workspace:1.2.3`
+    Workspace(String),
+    /// `catalog:` or `catalog:<name>`
+    Catalog(String),
+    /// `npm:react@^17`
+    Alias { name: String, spec: Box<Spec> },
+    /// `file:../foo` or a bare path (`./foo`)
+    File(String),
+    /// `link:../foo`
+    Link(String),
+    /// `portal:../foo`
+    Portal(String),
+    /// Git urls and hosted shorthands (`user/repo#ref`, `github:user/repo`)
+    Git {
+        url: String,
+        committish: Option<String>,
+    },
+    /// `https://example.com/foo-1.0.0.tgz`
+    Tarball(String),
+    /// Anything else, eg: an unsupported protocol
+    Unknown(String),
+}
+
+const GIT_PROTOCOLS: [&str; 8] = [
+    "git+ssh://",
+    "git+https://",
+    "git+http://",
+    "git+file://",
+    "git://",
+    "ssh://",
+    "git@",
+    "git+",
+];
+
+const HOSTED_PREFIXES: [&str; 4] = ["github:", "gitlab:", "bitbucket:", "gist:"];
+
+impl Spec {
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+
+        if let Some(rest) = s.strip_prefix("workspace:") {
+            return Spec::Workspace(rest.to_string());
+        }
+
+        if let Some(rest) = s.strip_prefix(CATALOG_PROTOCOL) {
+            return Spec::Catalog(rest.to_string());
+        }
+
+        if let Some(rest) = s.strip_prefix("npm:") {
+            let at = rest.char_indices().skip(1).find(|(_, c)| *c == '@');
+            let (name, spec) = match at {
+                Some((i, _)) => (&rest[..i], &rest[i + 1..]),
+                None => (rest, "*"),
+            };
+
+            return Spec::Alias {
+                name: name.to_string(),
+                spec: Box::new(Spec::parse(spec)),
+            };
+        }
+
+        if let Some(rest) = s.strip_prefix("file:") {
+            return Spec::File(rest.to_string());
+        }
+
+        if let Some(rest) = s.strip_prefix("link:") {
+            return Spec::Link(rest.to_string());
+        }
+
+        if let Some(rest) = s.strip_prefix("portal:") {
+            return Spec::Portal(rest.to_string());
+        }
+
+        // before git shorthands, which `../foo` would otherwise look like
+        if s.starts_with("./") || s.starts_with("../") || s.starts_with('/') || s.starts_with("~/")
+        {
+            return Spec::File(s.to_string());
+        }
+
+        let is_git = GIT_PROTOCOLS.iter().any(|p| s.starts_with(p))
+            || HOSTED_PREFIXES.iter().any(|p| s.starts_with(p))
+            || s.split('#').next().unwrap_or_default().ends_with(".git");
+
+        if is_git || is_hosted_shorthand(s) {
+            let (url, committish) = match s.split_once('#') {
+                Some((url, committish)) => (url, Some(committish.to_string())),
+                None => (s, None),
+            };
+
+            return Spec::Git {
+                url: url.to_string(),
+                committish: committish.filter(|c| !c.is_empty()),
+            };
+        }
+
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return Spec::Tarball(s.to_string());
+        }
+
+        if VersionRange::from_str(s).is_ok() {
+            return Spec::Semver(s.to_string());
+        }
+
+        // dist tags cannot be valid ranges and cannot contain whitespace or protocols
+        if !s.contains(char::is_whitespace) && !s.contains(':') {
+            return Spec::Tag(s.to_string());
+        }
+
+        Spec::Unknown(s.to_string())
+    }
+
+    pub fn kind(&self) -> SpecKind {
+        match self {
+            Spec::Semver(_) => SpecKind::Semver,
+            Spec::Tag(_) => SpecKind::Tag,
+            Spec::Workspace(_) => SpecKind::Workspace,
+            Spec::Catalog(_) => SpecKind::Catalog,
+            Spec::Alias { .. } => SpecKind::Alias,
+            Spec::File(_) => SpecKind::File,
+            Spec::Link(_) => SpecKind::Link,
+            Spec::Portal(_) => SpecKind::Portal,
+            Spec::Git { .. } => SpecKind::Git,
+            Spec::Tarball(_) => SpecKind::Tarball,
+            Spec::Unknown(_) => SpecKind::Unknown,
+        }
+    }
+
+    /// Real package name and spec behind an `npm:` alias
+    pub fn dealias(&self) -> Option<(&str, &Spec)> {
+        match self {
+            Spec::Alias { name, spec } => Some((name, spec)),
+            _ => None,
+        }
+    }
+
+    /// Normalized source of git, tarball and path dependencies.
+    ///
+    /// Relative paths are resolved against `dir`, the directory of the
+    /// manifest declaring the dependency.
+    pub fn source(&self, dir: &Path) -> Option<Source> {
+        match self {
+            Spec::Git { url, committish } => Some(Source {
+                location: normalize_git_url(url),
+                reference: committish.clone(),
+            }),
+            Spec::Tarball(url) => Some(tarball_source(url)),
+            Spec::File(path) | Spec::Link(path) | Spec::Portal(path) => {
+                let path = normalize_path(&dir.join(path));
+                let path = path.to_string_lossy().replace('\\', "/");
+
+                match path.ends_with(".tgz") || path.ends_with(".tar.gz") {
+                    true => Some(tarball_source(&path)),
+                    false => Some(Source {
+                        location: path,
+                        reference: None,
+                    }),
+                }
+            }
+            Spec::Alias { spec, .. } => spec.source(dir),
+            _ => None,
+        }
+    }
+
+    /// Version used to compare specs across workspaces: the lowest version
+    /// allowed by the range (`~4.17.1` => `4.17.1`, `>=1 <2` => `1.0.0`), or
+    /// the range itself when it has no lower bound (`*`, `<2`).
+    ///
+    /// Only semver ranges (and workspace references) can be compared,
+    /// every other spec returns `None`.
+    pub fn version(&self) -> Option<String> {
+        match self {
+            Spec::Workspace(_) => Some("workspace".to_string()),
+            Spec::Semver(range) => match self.range().and_then(|r| r.lower_bound()) {
+                Some(version) => Some(version.to_string()),
+                None => Some(range.clone()),
+            },
+            Spec::Alias { spec, .. } => spec.version(),
+            _ => None,
+        }
+    }
+
+    /// The semver range of the spec, looking through aliases
+    pub fn range(&self) -> Option<VersionRange> {
+        match self {
+            Spec::Semver(range) => VersionRange::from_str(range).ok(),
+            Spec::Alias { spec, .. } => spec.range(),
+            _ => None,
+        }
+    }
+}
+
+/// Reduces the many forms of a git url to `host/user/repo`
+fn normalize_git_url(url: &str) -> String {
+    let hosted = [
+        ("github:", "github.com/"),
+        ("gitlab:", "gitlab.com/"),
+        ("bitbucket:", "bitbucket.org/"),
+        ("gist:", "gist.github.com/"),
+    ];
+
+    for (prefix, host) in hosted {
+        if let Some(rest) = url.strip_prefix(prefix) {
+            return format!("{}{}", host, rest.trim_end_matches(".git"));
+        }
+    }
+
+    let mut url = url.strip_prefix("git+").unwrap_or(url);
+
+    if let Some((_, rest)) = url.split_once("://") {
+        url = rest;
+    }
+
+    // drop credentials (`git@github.com:user/repo`)
+    if let Some((auth, rest)) = url.split_once('@') {
+        if !auth.contains('/') {
+            url = rest;
+        }
+    }
+
+    let url = match url.split_once('/') {
+        // scp-like syntax (`github.com:user/repo`) or explicit port (`host:22/user/repo`)
+        Some((host, rest)) if host.contains(':') => {
+            let (host, port) = host.split_once(':').unwrap_or_default();
+
+            match port.chars().all(|c| c.is_ascii_digit()) {
+                true => format!("{}/{}", host, rest),
+                false => format!("{}/{}/{}", host, port, rest),
+            }
+        }
+        _ => url.to_string(),
+    };
+
+    // hosted shorthand (`user/repo`) defaults to GitHub
+    let url = match url.split('/').next() {
+        Some(first) if !first.contains('.') && is_hosted_shorthand(&url) => {
+            format!("github.com/{}", url)
+        }
+        _ => url,
+    };
+
+    let (host, path) = url.split_once('/').unwrap_or((&url, ""));
+    let path = path.trim_end_matches('/').trim_end_matches(".git");
+
+    format!("{}/{}", host.to_lowercase(), path)
+}
+
+/// Splits a tarball into the location it comes from and its version.
+///
+/// GitHub archives (`/archive/<ref>.tar.gz`, `/tarball/<ref>`) are reported as
+/// the repository they belong to, other tarballs drop the version from the
+/// file name (`foo-1.2.3.tgz` => `foo`).
+fn tarball_source(url: &str) -> Source {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let stripped = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+
+    for marker in ["/archive/", "/tarball/", "/tar.gz/"] {
+        if let Some((repo, reference)) = stripped.split_once(marker) {
+            let repo = repo.replacen("codeload.github.com", "github.com", 1);
+            let reference = reference
+                .trim_end_matches(".tar.gz")
+                .trim_end_matches(".tgz")
+                .trim_end_matches(".zip");
+
+            return Source {
+                location: repo,
+                reference: Some(reference.to_string()),
+            };
+        }
+    }
+
+    let (dir, file) = stripped.rsplit_once('/').unwrap_or(("", stripped));
+    let file = file.trim_end_matches(".tgz").trim_end_matches(".tar.gz");
+
+    // `name-1.2.3` where the version starts at the first `-<digit>`
+    let split = file
+        .char_indices()
+        .find(|(i, c)| *c == '-' && file[i + 1..].starts_with(|c: char| c.is_ascii_digit()));
+
+    let (name, version) = match split {
+        Some((i, _)) => (&file[..i], Some(file[i + 1..].to_string())),
+        None => (file, None),
+    };
+
+    Source {
+        location: format!("{}/{}", dir, name)
+            .trim_start_matches('/')
+            .to_string(),
+        reference: version,
+    }
+}
+
+/// Lexically resolves `.` and `..` without touching the filesystem
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push("..");
+                }
+            }
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// `user/repo` or `user/repo#ref`
+fn is_hosted_shorthand(s: &str) -> bool {
+    let repo = s.split('#').next().unwrap_or_default();
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+    };
+
+    match repo.split_once('/') {
+        Some((user, project)) => !s.starts_with('@') && valid(user) && valid(project),
+        None => false,
+    }
+}
+
+impl FromStr for Spec {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+
+impl fmt::Display for Spec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Spec::Semver(range) => write!(f, "{}", range),
+            Spec::Tag(tag) => write!(f, "{}", tag),
+            Spec::Workspace(range) => write!(f, "workspace:{}", range),
+            Spec::Catalog(name) => write!(f, "{}{}", CATALOG_PROTOCOL, name),
+            Spec::Alias { name, spec } => write!(f, "npm:{}@{}", name, spec),
+            Spec::File(path) => write!(f, "file:{}", path),
+            Spec::Link(path) => write!(f, "link:{}", path),
+            Spec::Portal(path) => write!(f, "portal:{}", path),
+            Spec::Git { url, committish } => match committish {
+                Some(committish) => write!(f, "{}#{}", url, committish),
+                None => write!(f, "{}", url),
+            },
+            Spec::Tarball(url) => write!(f, "{}", url),
+            Spec::Unknown(spec) => write!(f, "{}", spec),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(spec: &str) -> Option<String> {
+        Spec::parse(spec).version()
+    }
+
+    #[test]
+    fn parse_kinds() {
+        assert_eq!(Spec::parse("~4.17.1").kind(), SpecKind::Semver);
+        assert_eq!(Spec::parse(">=1 <2").kind(), SpecKind::Semver);
+        assert_eq!(Spec::parse("latest").kind(), SpecKind::Tag);
+        assert_eq!(Spec::parse("workspace:^").kind(), SpecKind::Workspace);
+        assert_eq!(Spec::parse("catalog:").kind(), SpecKind::Catalog);
+        assert_eq!(Spec::parse("file:../a").kind(), SpecKind::File);
+        assert_eq!(Spec::parse("./a").kind(), SpecKind::File);
+        assert_eq!(Spec::parse("link:../a").kind(), SpecKind::Link);
+        assert_eq!(Spec::parse("user/repo#v1").kind(), SpecKind::Git);
+        assert_eq!(Spec::parse("git@github.com:u/r.git").kind(), SpecKind::Git);
+        assert_eq!(
+            Spec::parse("https://example.com/a-1.0.0.tgz").kind(),
+            SpecKind::Tarball
+        );
+    }
+
+    #[test]
+    fn parse_alias() {
+        let spec = Spec::parse("npm:@scope/react@^17.0.2");
+        let (name, inner) = spec.dealias().unwrap();
+
+        assert_eq!(name, "@scope/react");
+        assert_eq!(inner, &Spec::Semver("^17.0.2".into()));
+        assert_eq!(spec.to_string(), "npm:@scope/react@^17.0.2");
+        assert_eq!(
+            Spec::parse("npm:react").dealias().unwrap().1.to_string(),
+            "*"
+        );
+    }
+
+    #[test]
+    fn parse_git() {
+        assert_eq!(
+            Spec::parse("github:user/repo#main"),
+            Spec::Git {
+                url: "github:user/repo".into(),
+                committish: Some("main".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn version_of_ranges() {
+        assert_eq!(version("^1.2.3").as_deref(), Some("1.2.3"));
+        assert_eq!(version("~4.17.1").as_deref(), Some("4.17.1"));
+        assert_eq!(version("~4.16.1").as_deref(), Some("4.16.1"));
+        assert_eq!(version("1.2.3").as_deref(), Some("1.2.3"));
+        assert_eq!(version("^18").as_deref(), Some("18.0.0"));
+        assert_eq!(version(">=1 <2").as_deref(), Some("1.0.0"));
+        assert_eq!(version("1.x").as_deref(), Some("1.0.0"));
+        assert_eq!(version("^2.0.0-rc.1").as_deref(), Some("2.0.0-rc.1"));
+        assert_eq!(version("*").as_deref(), Some("*"));
+        assert_eq!(version("<2").as_deref(), Some("<2"));
+    }
+
+    #[test]
+    fn version_of_other_specs() {
+        assert_eq!(version("npm:react@~17.0.2").as_deref(), Some("17.0.2"));
+        assert_eq!(version("workspace:^").as_deref(), Some("workspace"));
+        assert_eq!(version("user/repo#v1"), None);
+        assert_eq!(version("file:../a"), None);
+        assert_eq!(version("latest"), None);
+    }
+}
@@ -1,10 +1,61 @@
+use std::collections::HashMap;
+
 use serde::Deserialize;
 
 use super::file::*;
+use super::overrides::{self, Override};
+
+pub const CATALOG_PROTOCOL: &str = "catalog:";
+
+/// Name of the catalog referenced by a bare `catalog:` spec
+pub const DEFAULT_CATALOG: &str = "default";
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct Workspace {
     pub packages: Vec<String>,
+
+    #[serde(default)]
+    pub overrides: HashMap<String, String>,
+
+    /// The default catalog (`catalog:` or `catalog:default`)
+    #[serde(default)]
+    pub catalog: HashMap<String, String>,
+
+    /// Named catalogs (`catalog:<name>`)
+    #[serde(default)]
+    pub catalogs: HashMap<String, HashMap<String, String>>,
+}
+
+impl Workspace {
+    /// `root` holds the direct dependencies of the root manifest, which
+    /// `$name` versions refer to
+    pub fn get_overrides(&self, root: &HashMap<String, String>) -> Vec<Override> {
+        overrides::parse_pnpm(&self.overrides, root)
+    }
+
+    /// Returns the catalog with the given name, `default` being either
+    /// the top level `catalog` or `catalogs.default`.
+    pub fn get_catalog(&self, name: &str) -> Option<&HashMap<String, String>> {
+        match name {
+            DEFAULT_CATALOG if !self.catalog.is_empty() => Some(&self.catalog),
+            name => self.catalogs.get(name),
+        }
+    }
+
+    /// Resolves a `catalog:` spec to the range defined in the catalog.
+    ///
+    /// Returns `None` if the spec does not use the catalog protocol or
+    /// the dependency is missing from the referenced catalog.
+    pub fn resolve_catalog(&self, dependency: &str, spec: &str) -> Option<&String> {
+        let name = spec.strip_prefix(CATALOG_PROTOCOL)?.trim();
+        let name = if name.is_empty() {
+            DEFAULT_CATALOG
+        } else {
+            name
+        };
+
+        self.get_catalog(name)?.get(dependency)
+    }
 }
 
 impl File<Workspace> for Workspace {}
@@ -0,0 +1,413 @@
+//! Discovers the workspaces of a monorepo and parses their manifests.
+//!
+//! Paths are joined to the root of the monorepo, a root of `.` keeps them
+//! relative to the current directory.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use glob::glob;
+use rayon::prelude::*;
+
+use crate::cache::{Cache, Key, Lookup};
+use crate::git::{self, GitError};
+use crate::log;
+use crate::models::file::{self, File, FileError};
+use crate::models::lockfile::{importer_path, Lockfile, LockfileKind};
+use crate::models::package_json::PackageJson;
+use crate::models::resolver::Resolver;
+use crate::models::source_map::SourceMap;
+use crate::models::workspace::Workspace;
+
+#[derive(thiserror::Error, Debug)]
+pub enum MonorepoError {
+    #[error("Workspace file not found: {}", .0.display())]
+    NotFound(PathBuf),
+
+    #[error("No package.json at {0}")]
+    NotFoundAt(String),
+
+    #[error("Cannot read {}: {}", .0.display(), .1)]
+    Invalid(PathBuf, FileError),
+
+    #[error("No lockfile found (pnpm-lock.yaml, package-lock.json or yarn.lock)")]
+    NoLockfile,
+
+    #[error(transparent)]
+    File(#[from] FileError),
+
+    #[error(transparent)]
+    Pattern(#[from] glob::PatternError),
+
+    #[error(transparent)]
+    Glob(#[from] glob::GlobError),
+
+    #[error(transparent)]
+    Git(#[from] GitError),
+}
+
+impl From<serde_yaml::Error> for MonorepoError {
+    fn from(e: serde_yaml::Error) -> Self {
+        MonorepoError::File(FileError::from(e))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, MonorepoError>;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum MonorepoKind {
+    #[default]
+    Generic,
+    Pnpm,
+}
+
+impl MonorepoKind {
+    /// `pnpm-workspace.yaml` makes a pnpm monorepo, a `package.json` alone a
+    /// npm or yarn one
+    pub fn detect(root: &Path) -> Option<MonorepoKind> {
+        if join(root, "pnpm-workspace.yaml").exists() {
+            return Some(MonorepoKind::Pnpm);
+        }
+
+        match join(root, "package.json").exists() {
+            true => Some(MonorepoKind::Generic),
+            false => None,
+        }
+    }
+}
+
+/// `path` inside `root`, left untouched when `root` is the current directory
+fn join(root: &Path, path: impl AsRef<Path>) -> PathBuf {
+    match root == Path::new(".") {
+        true => path.as_ref().to_path_buf(),
+        false => root.join(path),
+    }
+}
+
+/// Glob patterns of the workspaces
+pub fn get_packages(root: &PackageJson, workspace: Option<&Workspace>) -> Vec<String> {
+    match workspace {
+        Some(workspace) => workspace.packages.clone(),
+        None => root.workspaces.clone().unwrap_or_default(),
+    }
+}
+
+/// Parses `pnpm-workspace.yaml`, a broken file is an error rather than a
+/// monorepo without workspaces
+fn get_workspace(root: &Path, kind: MonorepoKind) -> Result<Option<Workspace>> {
+    match kind {
+        MonorepoKind::Generic => Ok(None),
+        MonorepoKind::Pnpm => {
+            let path = join(root, "pnpm-workspace.yaml");
+            match Workspace::load(&path) {
+                Ok(workspace) => Ok(Some(workspace)),
+                Err(e) => Err(MonorepoError::Invalid(path, e)),
+            }
+        }
+    }
+}
+
+/// Manifests matched by the workspace patterns, `!` patterns exclude
+/// directories. Patterns are expanded in parallel.
+fn discover(root: &Path, patterns: &[String]) -> Result<BTreeSet<PathBuf>> {
+    let (excluded, included): (Vec<_>, Vec<_>) = patterns.iter().partition(|p| p.starts_with('!'));
+
+    let excluded = excluded
+        .iter()
+        .map(|p| glob::Pattern::new(p[1..].trim_end_matches('/')))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let found = included
+        .par_iter()
+        .map(|pattern| {
+            let pattern = join(
+                root,
+                format!("{}/package.json", pattern.trim_end_matches('/')),
+            );
+            glob(&pattern.to_string_lossy())?
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(MonorepoError::from)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(found
+        .into_iter()
+        .flatten()
+        .filter(|path| {
+            let path = path.strip_prefix(root).unwrap_or(path);
+            !path.components().any(|c| c.as_os_str() == "node_modules")
+        })
+        .filter(|path| {
+            let path = path.strip_prefix(root).unwrap_or(path);
+            let dir = path.parent().unwrap_or(Path::new(""));
+            !excluded.iter().any(|p| p.matches_path(dir))
+        })
+        .collect())
+}
+
+/// Manifests are cached along with their source map, which they do not serialize
+type ManifestCache = Cache<(PackageJson, SourceMap)>;
+
+/// The manifest at `path`, with the key to cache it with when it was parsed
+fn load_manifest(
+    path: &Path,
+    cache: Option<&ManifestCache>,
+) -> file::Result<(PackageJson, Option<Key>)> {
+    let key = match cache.map(|c| c.get(path)) {
+        Some(Lookup::Hit((manifest, source_map))) => {
+            let manifest = PackageJson {
+                path: path.to_path_buf(),
+                source_map: source_map.clone(),
+                ..manifest.clone()
+            };
+            return Ok((manifest, None));
+        }
+        Some(Lookup::Miss(key)) => key,
+        None => None,
+    };
+
+    Ok((PackageJson::load(path)?, key))
+}
+
+fn build_resolver(root: Option<&PackageJson>, workspace: Option<Workspace>) -> Resolver {
+    let mut overrides = root.map(|pkg| pkg.get_overrides()).unwrap_or_default();
+
+    if let Some(workspace) = &workspace {
+        let dependencies = root
+            .map(|pkg| pkg.direct_dependencies())
+            .unwrap_or_default();
+        overrides.extend(workspace.get_overrides(&dependencies));
+    }
+
+    Resolver::new(overrides, workspace)
+}
+
+/// The manifests of a monorepo, read once and shared by every analysis
+#[derive(Debug, Clone)]
+pub struct Monorepo {
+    pub root: PathBuf,
+    pub kind: MonorepoKind,
+
+    /// Glob patterns of the workspaces
+    pub patterns: Vec<String>,
+
+    /// `pnpm-workspace.yaml`, if any
+    pub workspace: Option<Workspace>,
+
+    /// Resolves the overrides and catalogs of the monorepo
+    pub resolver: Resolver,
+
+    /// Manifests of the workspaces followed by the root one
+    manifests: Vec<PackageJson>,
+}
+
+impl Monorepo {
+    /// Reads the monorepo whose root manifest is in `root`
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use monocheck::monorepo::Monorepo;
+    ///
+    /// let monorepo = Monorepo::discover(Path::new("."))?;
+    ///
+    /// for manifest in monorepo.manifests(false) {
+    ///     println!("{}", manifest.name);
+    /// }
+    /// # Ok::<(), monocheck::monorepo::MonorepoError>(())
+    /// ```
+    pub fn discover(root: &Path) -> Result<Monorepo> {
+        Monorepo::scan(root, None)
+    }
+
+    /// Discovers and parses the manifests of the workspaces in parallel,
+    /// reusing the ones cached in `cache` when it is set
+    pub fn scan(root: &Path, cache: Option<&Path>) -> Result<Monorepo> {
+        let kind = match MonorepoKind::detect(root) {
+            Some(kind) => kind,
+            None => return Err(MonorepoError::NotFound(join(root, "package.json"))),
+        };
+
+        let mut cache: Option<ManifestCache> = cache.map(|dir| Cache::open(dir, "manifests"));
+
+        let (root_manifest, root_key) = load_manifest(&join(root, "package.json"), cache.as_ref())?;
+        let workspace = get_workspace(root, kind)?;
+
+        let patterns = get_packages(&root_manifest, workspace.as_ref());
+        let paths = discover(root, &patterns)?;
+
+        let (mut manifests, mut keys): (Vec<_>, Vec<_>) = paths
+            .par_iter()
+            .map(|path| load_manifest(path, cache.as_ref()))
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+
+        let resolver = build_resolver(Some(&root_manifest), workspace.clone());
+        manifests.push(root_manifest);
+        keys.push(root_key);
+
+        if let Some(cache) = cache.as_mut() {
+            for (manifest, key) in manifests.iter().zip(keys) {
+                if let Some(key) = key {
+                    let entry = (manifest.clone(), manifest.source_map.clone());
+                    cache.insert(&manifest.path, key, entry);
+                }
+            }
+
+            if let Err(e) = cache.save() {
+                log::error("Cannot write the cache", e);
+            }
+        }
+
+        Ok(Monorepo {
+            root: root.to_path_buf(),
+            kind,
+            patterns,
+            workspace,
+            resolver,
+            manifests,
+        })
+    }
+
+    /// The monorepo as committed at a git revision
+    pub fn at_revision(root: &Path, rev: &str) -> Result<Monorepo> {
+        let root_manifest = match git::show(root, rev, "package.json")? {
+            Some(text) => PackageJson::parse(&join(root, "package.json"), &text)?,
+            None => return Err(MonorepoError::NotFoundAt(rev.to_string())),
+        };
+
+        let workspace: Option<Workspace> = match git::show(root, rev, "pnpm-workspace.yaml")? {
+            Some(text) => Some(serde_yaml::from_str(&text)?),
+            None => None,
+        };
+
+        let kind = match workspace {
+            Some(_) => MonorepoKind::Pnpm,
+            None => MonorepoKind::Generic,
+        };
+
+        let patterns = get_packages(&root_manifest, workspace.as_ref());
+
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        // `!packages/a` excludes the manifests matched by `packages/*`
+        let globs = patterns
+            .iter()
+            .map(|p| {
+                let pattern = format!(
+                    "{}/package.json",
+                    p.trim_start_matches('!').trim_end_matches('/')
+                );
+                glob::Pattern::new(&pattern).map(|pattern| (p.starts_with('!'), pattern))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let matches = |file: &String, excluded: bool| {
+            globs
+                .iter()
+                .any(|(e, p)| *e == excluded && p.matches_with(file, options))
+        };
+
+        let files: Vec<String> = git::files(root, rev)?
+            .into_iter()
+            .filter(|file| matches(file, false) && !matches(file, true))
+            .filter(|file| !file.split('/').any(|c| c == "node_modules"))
+            .collect();
+
+        let mut manifests = files
+            .par_iter()
+            .filter_map(|file| match git::show(root, rev, file) {
+                Ok(Some(text)) => {
+                    Some(PackageJson::parse(&join(root, file), &text).map_err(Into::into))
+                }
+                Ok(None) => None,
+                Err(e) => Some(Err(e.into())),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let resolver = build_resolver(Some(&root_manifest), workspace.clone());
+        manifests.push(root_manifest);
+
+        Ok(Monorepo {
+            root: root.to_path_buf(),
+            kind,
+            patterns,
+            workspace,
+            resolver,
+            manifests,
+        })
+    }
+
+    /// Manifests of the workspaces, including the root one if asked to
+    pub fn manifests(&self, include_root: bool) -> &[PackageJson] {
+        match include_root {
+            true => &self.manifests,
+            false => &self.manifests[..self.manifests.len() - 1],
+        }
+    }
+
+    /// `path` inside the monorepo
+    pub fn path(&self, path: impl AsRef<Path>) -> PathBuf {
+        join(&self.root, path)
+    }
+
+    /// `path` relative to the root of the monorepo
+    pub fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    /// Files the monorepo is read from: manifests, workspace file and lockfiles,
+    /// including the ones that do not exist yet. The manifests are the ones
+    /// found by the last scan, the patterns are not expanded again.
+    pub fn files(&self) -> BTreeSet<PathBuf> {
+        let mut paths: BTreeSet<PathBuf> = self.manifests.iter().map(|m| m.path.clone()).collect();
+        paths.insert(self.path("package.json"));
+        paths.insert(self.path("pnpm-workspace.yaml"));
+
+        for kind in LockfileKind::ALL {
+            paths.insert(self.path(kind.file_name()));
+        }
+
+        paths
+    }
+
+    /// Importer path => workspace name
+    pub fn importers(&self) -> BTreeMap<String, String> {
+        self.manifests
+            .iter()
+            .map(|m| (importer_path(self.relative(&m.path)), m.name.clone()))
+            .collect()
+    }
+
+    /// The lockfile of the monorepo, linked to the manifests it was generated
+    /// from, reusing the one cached in `cache` when it is set
+    pub fn lockfile(&self, cache: Option<&Path>) -> Result<Lockfile> {
+        let path = match Lockfile::find(&self.root) {
+            Some(path) => path,
+            None => return Err(MonorepoError::NoLockfile),
+        };
+
+        let mut cache: Option<Cache<Lockfile>> = cache.map(|dir| Cache::open(dir, "lockfile"));
+
+        let (mut lockfile, key) = match cache.as_ref().map(|c| c.get(&path)) {
+            Some(Lookup::Hit(lockfile)) => (lockfile.clone(), None),
+            Some(Lookup::Miss(key)) => (Lockfile::load(&path)?, key),
+            None => (Lockfile::load(&path)?, None),
+        };
+
+        if let (Some(cache), Some(key)) = (cache.as_mut(), key) {
+            cache.insert(&path, key, lockfile.clone());
+
+            if let Err(e) = cache.save() {
+                log::error("Cannot write the cache", e);
+            }
+        }
+
+        lockfile.link_manifests(&self.manifests);
+
+        Ok(lockfile)
+    }
+}
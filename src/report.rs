@@ -0,0 +1,1121 @@
+use std::collections::BTreeSet;
+
+use colored::Color;
+use serde::{Serialize, Serializer};
+
+use crate::format::{self, Finding, Level, Render, Sortable};
+use crate::models::package_json::Section;
+use crate::models::source_map::Location;
+use crate::rules::catalog::CatalogSuggestion;
+use crate::rules::diff::{ChangeKind, DependencyChange};
+use crate::rules::drift::{Drift, DriftKind};
+use crate::rules::overrides::OverrideUsage;
+use crate::rules::peer_drift::PeerDrift;
+use crate::rules::sources::SourceConflict;
+use crate::rules::transitive::TransitiveDuplicate;
+use crate::rules::why::Chain;
+use crate::Sort;
+
+/// A single declaration of a dependency by a workspace
+#[derive(Debug, Serialize, Clone, PartialEq, Eq, Hash)]
+pub struct Usage {
+    pub workspace: String,
+    pub section: Section,
+
+    /// The spec as declared in the manifest
+    pub spec: String,
+
+    /// Version used for comparisons, `None` for non-semver specs
+    pub version: Option<String>,
+
+    /// Name the dependency is declared as through an `npm:` alias
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+
+    /// Where the dependency is declared
+    pub location: Option<Location>,
+}
+
+/// A dependency of the duplicates report
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateEntry {
+    pub name: String,
+    pub count: usize,
+    pub workspaces: Vec<String>,
+    pub versions: Vec<String>,
+    pub specs: Vec<String>,
+    pub aliases: Vec<String>,
+    pub usages: Vec<Usage>,
+}
+
+impl DuplicateEntry {
+    /// Whether workspaces declare different versions or specs
+    pub fn is_conflict(&self) -> bool {
+        self.versions.len() + self.specs.len() > 1
+    }
+
+    /// Conflicts are errors and plain duplicates warnings, in every format
+    pub fn level(&self) -> Level {
+        match self.is_conflict() {
+            true => Level::Error,
+            false => Level::Warning,
+        }
+    }
+}
+
+impl Sortable for DuplicateEntry {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    fn versions(&self) -> usize {
+        self.versions.len() + self.specs.len()
+    }
+
+    fn severity(&self) -> Option<Level> {
+        match self.is_conflict() {
+            true => Some(Level::Error),
+            false => Some(Level::Warning),
+        }
+    }
+}
+
+/// Joins the values of a set or list for a table cell
+fn join<'a>(values: impl IntoIterator<Item = &'a String>, separator: &str) -> String {
+    values
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Human readable size, eg: `1.2 MB`
+fn size(bytes: u64) -> String {
+    let units = ["B", "kB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1000.0 && unit < units.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} {}", bytes, units[0]),
+        _ => format!("{:.1} {}", value, units[unit]),
+    }
+}
+
+/// The default report: dependencies declared by multiple workspaces
+#[derive(Debug, Clone)]
+pub struct Duplicates {
+    /// Every checked dependency, including the ones below `min`
+    pub entries: Vec<DuplicateEntry>,
+    pub min: usize,
+
+    /// Show versions and specs in the table output
+    pub deep: bool,
+}
+
+impl Duplicates {
+    /// Entries declared by at least `min` workspaces
+    pub fn duplicated(&self) -> Vec<DuplicateEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.count >= self.min)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Serialize for Duplicates {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.duplicated())
+    }
+}
+
+impl Render for Duplicates {
+    fn title(&self) -> &'static str {
+        "duplicates"
+    }
+
+    fn header(&self) -> Vec<&'static str> {
+        match self.deep {
+            true => vec!["Dependency", "Count", "Versions", "Specs", "Workspaces"],
+            false => vec!["Dependency", "Count", "Packages"],
+        }
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.duplicated()
+            .into_iter()
+            .map(|entry| {
+                let name = match entry.aliases.is_empty() {
+                    true => entry.name.clone(),
+                    false => format!("{} (as {})", entry.name, entry.aliases.join(", ")),
+                };
+
+                match self.deep {
+                    true => vec![
+                        name,
+                        entry.count.to_string(),
+                        entry.versions.len().to_string(),
+                        entry.specs.join("\n"),
+                        entry.workspaces.join(", "),
+                    ],
+                    false => vec![name, entry.count.to_string(), entry.workspaces.join(", ")],
+                }
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        match self.duplicated().len() {
+            0 => format!("No duplicate dependencies found (min: {})", self.min),
+            total => format!("Total : {}", total),
+        }
+    }
+
+    fn sort(&mut self, sort: Sort, reverse: bool) {
+        format::sort(&mut self.entries, sort, reverse);
+    }
+
+    fn markdown(&self) -> String {
+        format::markdown::duplicates(&self.duplicated(), self.min)
+    }
+
+    fn csv(&self, tsv: bool) -> String {
+        let entries = self.duplicated();
+        let rows = entries
+            .iter()
+            .flat_map(|e| e.usages.iter().map(|u| (e.name.as_str(), u)));
+
+        format::csv::usages(rows, tsv)
+    }
+
+    fn sarif(&self) -> serde_json::Value {
+        format::sarif::duplicates(&self.duplicated())
+    }
+
+    /// JUnit reports every checked dependency, passing or not
+    fn junit(&self) -> String {
+        format::junit::duplicates(&self.entries, self.min)
+    }
+
+    fn github(&self) -> String {
+        format::github::duplicates(&self.duplicated())
+    }
+
+    fn html(&self) -> String {
+        format::html::duplicates(&self.duplicated(), self.min)
+    }
+
+    /// One finding per dependency, the formats above report every declaration
+    fn findings(&self) -> Vec<Finding> {
+        self.duplicated()
+            .into_iter()
+            .map(|entry| {
+                let (rule, title) = match entry.is_conflict() {
+                    true => (format::sarif::CONFLICT_RULE, "Version conflict"),
+                    false => (format::sarif::DUPLICATE_RULE, "Duplicate dependency"),
+                };
+
+                let usages = entry
+                    .usages
+                    .iter()
+                    .map(|u| format!("{} ({})", u.workspace, u.spec))
+                    .collect::<Vec<_>>();
+
+                Finding {
+                    rule,
+                    level: entry.level(),
+                    title: format!("{}: {}", title, entry.name),
+                    message: format!("{} is declared by {}", entry.name, usages.join(", ")),
+                    location: None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// A dependency matched by `search`
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchMatch {
+    pub name: String,
+    pub workspaces: Vec<String>,
+    pub usages: Vec<Usage>,
+}
+
+impl Sortable for SearchMatch {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self) -> usize {
+        self.workspaces.len()
+    }
+
+    fn versions(&self) -> usize {
+        self.usages
+            .iter()
+            .map(|u| u.version.as_ref().unwrap_or(&u.spec))
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    fn severity(&self) -> Option<Level> {
+        (self.versions() > 1).then_some(Level::Warning)
+    }
+}
+
+/// Result of the `search` command
+#[derive(Debug, Serialize, Clone)]
+#[serde(transparent)]
+pub struct Search(pub Vec<SearchMatch>);
+
+impl Render for Search {
+    fn title(&self) -> &'static str {
+        "search"
+    }
+
+    fn header(&self) -> Vec<&'static str> {
+        vec![
+            "Dependency",
+            "Workspace",
+            "Section",
+            "Spec",
+            "Version",
+            "Alias",
+        ]
+    }
+
+    /// One row per declaration, so the spec of every workspace is visible
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.0
+            .iter()
+            .flat_map(|m| {
+                m.usages.iter().map(|u| {
+                    vec![
+                        m.name.clone(),
+                        u.workspace.clone(),
+                        u.section.to_string(),
+                        u.spec.clone(),
+                        u.version.clone().unwrap_or("-".to_string()),
+                        u.alias.clone().unwrap_or("-".to_string()),
+                    ]
+                })
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        let declarations: usize = self.0.iter().map(|m| m.usages.len()).sum();
+
+        match self.0.len() {
+            0 => "No matching dependencies found".to_string(),
+            total => format!("Total : {} (declarations: {})", total, declarations),
+        }
+    }
+
+    fn sort(&mut self, sort: Sort, reverse: bool) {
+        format::sort(&mut self.0, sort, reverse);
+    }
+
+    fn csv(&self, tsv: bool) -> String {
+        let rows = self
+            .0
+            .iter()
+            .flat_map(|m| m.usages.iter().map(|u| (m.name.as_str(), u)));
+
+        format::csv::usages(rows, tsv)
+    }
+}
+
+impl Sortable for PeerDrift {
+    fn name(&self) -> &str {
+        &self.dependency
+    }
+
+    fn count(&self) -> usize {
+        1
+    }
+
+    fn versions(&self) -> usize {
+        1
+    }
+
+    fn severity(&self) -> Option<Level> {
+        Some(Level::Error)
+    }
+}
+
+/// Result of the `peers` command
+#[derive(Debug, Serialize, Clone)]
+#[serde(transparent)]
+pub struct PeerDrifts(pub Vec<PeerDrift>);
+
+impl Render for PeerDrifts {
+    fn title(&self) -> &'static str {
+        "peers"
+    }
+
+    fn header(&self) -> Vec<&'static str> {
+        vec!["Workspace", "Dependency", "Peer", "Dev"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.0
+            .iter()
+            .map(|d| {
+                vec![
+                    d.workspace.clone(),
+                    d.dependency.clone(),
+                    d.peer_range.clone(),
+                    d.dev_version.clone(),
+                ]
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        match self.0.len() {
+            0 => "No peer/dev version drift found".to_string(),
+            total => format!("Total : {}", total),
+        }
+    }
+
+    fn sort(&mut self, sort: Sort, reverse: bool) {
+        format::sort(&mut self.0, sort, reverse);
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        self.0
+            .iter()
+            .map(|d| Finding {
+                rule: "peer-drift",
+                level: Level::Error,
+                title: format!("Peer drift: {}", d.dependency),
+                message: format!(
+                    "{} tests {}@{} which is outside its peer range {}",
+                    d.workspace, d.dependency, d.dev_version, d.peer_range
+                ),
+                location: d.location.clone(),
+            })
+            .collect()
+    }
+}
+
+impl Sortable for OverrideUsage {
+    fn name(&self) -> &str {
+        &self.key
+    }
+
+    fn count(&self) -> usize {
+        self.workspaces.len()
+    }
+
+    fn versions(&self) -> usize {
+        1
+    }
+
+    fn severity(&self) -> Option<Level> {
+        match Overrides::status(self) {
+            "stale" => Some(Level::Warning),
+            "unverified" => Some(Level::Note),
+            _ => None,
+        }
+    }
+}
+
+/// Result of the `overrides` command
+#[derive(Debug, Serialize, Clone)]
+#[serde(transparent)]
+pub struct Overrides(pub Vec<OverrideUsage>);
+
+impl Overrides {
+    fn status(usage: &OverrideUsage) -> &'static str {
+        if usage.stale {
+            "stale"
+        } else if !usage.workspaces.is_empty() {
+            "applied"
+        } else if usage.locked {
+            "locked"
+        } else {
+            "unverified"
+        }
+    }
+}
+
+impl Render for Overrides {
+    fn title(&self) -> &'static str {
+        "overrides"
+    }
+
+    fn header(&self) -> Vec<&'static str> {
+        vec!["Source", "Override", "Version", "Workspaces", "Status"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.0
+            .iter()
+            .map(|u| {
+                vec![
+                    u.source.to_string(),
+                    u.key.clone(),
+                    u.version.clone(),
+                    join(&u.workspaces, ", "),
+                    Self::status(u).to_string(),
+                ]
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        let stale = self.0.iter().filter(|u| u.stale).count();
+
+        match self.0.len() {
+            0 => "No overrides found".to_string(),
+            total => format!("Total : {} (stale: {})", total, stale),
+        }
+    }
+
+    fn sort(&mut self, sort: Sort, reverse: bool) {
+        format::sort(&mut self.0, sort, reverse);
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        self.0
+            .iter()
+            .filter(|u| u.stale)
+            .map(|u| Finding {
+                rule: "stale-override",
+                level: Level::Warning,
+                title: format!("Stale override: {}", u.key),
+                message: format!(
+                    "{} override `{}` ({}) does not match any dependency",
+                    u.source, u.key, u.version
+                ),
+                location: None,
+            })
+            .collect()
+    }
+
+    fn color(&self, row: usize, column: usize) -> Option<Color> {
+        match (column, Self::status(&self.0[row])) {
+            (4, "stale") => Some(Color::Red),
+            (4, "unverified") => Some(Color::Yellow),
+            _ => None,
+        }
+    }
+}
+
+impl Sortable for SourceConflict {
+    fn name(&self) -> &str {
+        &self.location
+    }
+
+    fn count(&self) -> usize {
+        self.references
+            .values()
+            .flatten()
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    fn versions(&self) -> usize {
+        self.references.len()
+    }
+
+    fn severity(&self) -> Option<Level> {
+        Some(Level::Warning)
+    }
+}
+
+/// Result of the `sources` command
+#[derive(Debug, Serialize, Clone)]
+#[serde(transparent)]
+pub struct Sources(pub Vec<SourceConflict>);
+
+impl Render for Sources {
+    fn title(&self) -> &'static str {
+        "sources"
+    }
+
+    fn header(&self) -> Vec<&'static str> {
+        vec!["Source", "Kind", "Dependencies", "Ref", "Workspaces"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.0
+            .iter()
+            .flat_map(|conflict| {
+                conflict.references.iter().map(|(reference, workspaces)| {
+                    vec![
+                        conflict.location.clone(),
+                        conflict.kind.to_string(),
+                        join(&conflict.dependencies, ", "),
+                        reference.as_deref().unwrap_or("(default)").to_string(),
+                        join(workspaces, ", "),
+                    ]
+                })
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        match self.0.len() {
+            0 => "No inconsistent git, tarball or path dependencies found".to_string(),
+            total => format!("Total : {}", total),
+        }
+    }
+
+    fn sort(&mut self, sort: Sort, reverse: bool) {
+        format::sort(&mut self.0, sort, reverse);
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        self.0
+            .iter()
+            .map(|c| {
+                let references = c
+                    .references
+                    .iter()
+                    .map(|(reference, workspaces)| {
+                        format!(
+                            "{} ({})",
+                            reference.as_deref().unwrap_or("(default)"),
+                            join(workspaces, ", ")
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                Finding {
+                    rule: "inconsistent-source",
+                    level: Level::Warning,
+                    title: format!("Inconsistent source: {}", c.location),
+                    message: format!(
+                        "{} is referenced at different revisions: {}",
+                        c.location,
+                        references.join(", ")
+                    ),
+                    location: None,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Sortable for CatalogSuggestion {
+    fn name(&self) -> &str {
+        &self.dependency
+    }
+
+    fn count(&self) -> usize {
+        self.workspaces.len()
+    }
+
+    fn versions(&self) -> usize {
+        self.specs.len()
+    }
+
+    fn severity(&self) -> Option<Level> {
+        Some(Level::Note)
+    }
+}
+
+/// Result of the `catalog` command
+#[derive(Debug, Serialize, Clone)]
+#[serde(transparent)]
+pub struct Catalog {
+    pub suggestions: Vec<CatalogSuggestion>,
+
+    #[serde(skip)]
+    pub min: usize,
+}
+
+impl Render for Catalog {
+    fn title(&self) -> &'static str {
+        "catalog"
+    }
+
+    fn header(&self) -> Vec<&'static str> {
+        vec!["Dependency", "Catalog", "Specs", "Workspaces"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.suggestions
+            .iter()
+            .map(|s| {
+                let catalog = match s.in_catalog {
+                    true => format!("{} (existing)", s.suggested),
+                    false => s.suggested.clone(),
+                };
+
+                vec![
+                    s.dependency.clone(),
+                    catalog,
+                    join(&s.specs, ", "),
+                    join(&s.workspaces, ", "),
+                ]
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        match self.suggestions.len() {
+            0 => format!("No catalog candidates found (min: {})", self.min),
+            total => format!("Total : {}", total),
+        }
+    }
+
+    fn sort(&mut self, sort: Sort, reverse: bool) {
+        format::sort(&mut self.suggestions, sort, reverse);
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        self.suggestions
+            .iter()
+            .map(|s| Finding {
+                rule: "catalog-candidate",
+                level: Level::Note,
+                title: format!("Catalog candidate: {}", s.dependency),
+                message: format!(
+                    "{} can reference `catalog:` ({}) in {}",
+                    s.dependency,
+                    s.suggested,
+                    join(&s.workspaces, ", ")
+                ),
+                location: None,
+            })
+            .collect()
+    }
+
+    fn color(&self, row: usize, column: usize) -> Option<Color> {
+        match column == 1 && !self.suggestions[row].in_catalog {
+            true => Some(Color::Green),
+            false => None,
+        }
+    }
+}
+
+impl Sortable for DependencyChange {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self) -> usize {
+        1
+    }
+
+    fn versions(&self) -> usize {
+        self.after.len()
+    }
+
+    fn severity(&self) -> Option<Level> {
+        match self.kind {
+            ChangeKind::Duplicated => Some(Level::Warning),
+            ChangeKind::NewVersion => Some(Level::Note),
+            _ => None,
+        }
+    }
+}
+
+/// Result of the `diff` command
+#[derive(Debug, Serialize, Clone)]
+pub struct Diff {
+    pub before: String,
+    pub after: String,
+    pub changes: Vec<DependencyChange>,
+}
+
+impl Render for Diff {
+    fn title(&self) -> &'static str {
+        "diff"
+    }
+
+    fn header(&self) -> Vec<&'static str> {
+        vec!["Dependency", "Change", "Workspace", "Before", "After"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.changes
+            .iter()
+            .map(|c| {
+                vec![
+                    c.name.clone(),
+                    c.kind.as_str().to_string(),
+                    c.workspace.clone().unwrap_or_else(|| "-".to_string()),
+                    join(&c.before, ", "),
+                    join(&c.after, ", "),
+                ]
+            })
+            .collect()
+    }
+
+    fn color(&self, row: usize, column: usize) -> Option<Color> {
+        match (self.changes.get(row)?.kind, column) {
+            (ChangeKind::Duplicated, 1) => Some(Color::Red),
+            (ChangeKind::NewVersion, 1) => Some(Color::Yellow),
+            (ChangeKind::Aligned, 1) => Some(Color::Green),
+            _ => None,
+        }
+    }
+
+    fn summary(&self) -> String {
+        let count = |kind: ChangeKind| self.changes.iter().filter(|c| c.kind == kind).count();
+
+        match self.changes.len() {
+            0 => format!(
+                "No dependency changes between {} and {}",
+                self.before, self.after
+            ),
+            total => format!(
+                "Total : {} (duplicated: {}, aligned: {}, new versions: {})",
+                total,
+                count(ChangeKind::Duplicated),
+                count(ChangeKind::Aligned),
+                count(ChangeKind::NewVersion)
+            ),
+        }
+    }
+
+    fn sort(&mut self, sort: Sort, reverse: bool) {
+        format::sort(&mut self.changes, sort, reverse);
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        self.changes
+            .iter()
+            .filter_map(|c| {
+                let (rule, level, title) = match c.kind {
+                    ChangeKind::Duplicated => ("new-duplicate", Level::Warning, "New duplicate"),
+                    ChangeKind::NewVersion => ("new-version", Level::Note, "New version"),
+                    _ => return None,
+                };
+
+                Some(Finding {
+                    rule,
+                    level,
+                    title: format!("{}: {}", title, c.name),
+                    message: format!(
+                        "{} is declared in versions {} at {} (was {})",
+                        c.name,
+                        join(&c.after, ", "),
+                        self.after,
+                        join(&c.before, ", ")
+                    ),
+                    location: None,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Sortable for Drift {
+    fn name(&self) -> &str {
+        self.dependency.as_ref().unwrap_or(&self.workspace)
+    }
+
+    fn count(&self) -> usize {
+        1
+    }
+
+    fn versions(&self) -> usize {
+        1
+    }
+
+    fn severity(&self) -> Option<Level> {
+        Some(Level::Error)
+    }
+}
+
+/// Result of the `drift` command
+#[derive(Debug, Serialize, Clone)]
+#[serde(transparent)]
+pub struct Drifts(pub Vec<Drift>);
+
+impl Render for Drifts {
+    fn title(&self) -> &'static str {
+        "drift"
+    }
+
+    fn header(&self) -> Vec<&'static str> {
+        vec![
+            "Workspace",
+            "Problem",
+            "Dependency",
+            "Section",
+            "Declared",
+            "Locked",
+        ]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        let cell = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+
+        self.0
+            .iter()
+            .map(|d| {
+                vec![
+                    d.workspace.clone(),
+                    d.kind.as_str().to_string(),
+                    cell(&d.dependency),
+                    cell(&d.section.map(|s| s.to_string())),
+                    cell(&d.declared),
+                    cell(&d.locked),
+                ]
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        match self.0.len() {
+            0 => "The lockfile is up to date".to_string(),
+            total => format!("Total : {} (run install to update the lockfile)", total),
+        }
+    }
+
+    fn sort(&mut self, sort: Sort, reverse: bool) {
+        format::sort(&mut self.0, sort, reverse);
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        self.0
+            .iter()
+            .map(|d| {
+                let dependency = d.dependency.as_deref().unwrap_or_default();
+
+                let message = match (&d.declared, &d.locked) {
+                    (Some(declared), Some(locked)) => format!(
+                        "{} declares {}@{} but the lockfile was generated from {}",
+                        d.workspace, dependency, declared, locked
+                    ),
+                    (Some(declared), None) => format!(
+                        "{} declares {}@{} which is not in the lockfile",
+                        d.workspace, dependency, declared
+                    ),
+                    (None, Some(_)) => format!(
+                        "the lockfile still installs {} for {} which no longer declares it",
+                        dependency, d.workspace
+                    ),
+                    (None, None) => match d.kind {
+                        DriftKind::MissingImporter => {
+                            format!("{} is not in the lockfile", d.workspace)
+                        }
+                        _ => format!("{} is in the lockfile but is not a workspace", d.workspace),
+                    },
+                };
+
+                Finding {
+                    rule: "lockfile-drift",
+                    level: Level::Error,
+                    title: match &d.dependency {
+                        Some(dependency) => format!(
+                            "Lockfile drift: {} > {} ({})",
+                            d.workspace,
+                            dependency,
+                            d.kind.as_str()
+                        ),
+                        None => format!("Lockfile drift: {} ({})", d.workspace, d.kind.as_str()),
+                    },
+                    message,
+                    location: d.location.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Sortable for TransitiveDuplicate {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self) -> usize {
+        self.copies
+    }
+
+    fn versions(&self) -> usize {
+        self.versions.len()
+    }
+
+    fn severity(&self) -> Option<Level> {
+        Some(Level::Warning)
+    }
+
+    fn size(&self) -> u64 {
+        self.size.unwrap_or_default()
+    }
+}
+
+/// Result of the `transitive` command
+#[derive(Debug, Serialize, Clone)]
+#[serde(transparent)]
+pub struct Transitive(pub Vec<TransitiveDuplicate>);
+
+impl Render for Transitive {
+    fn title(&self) -> &'static str {
+        "transitive"
+    }
+
+    /// Most copies first, then the largest
+    fn default_sort(&self) -> Sort {
+        Sort::Count
+    }
+
+    fn header(&self) -> Vec<&'static str> {
+        vec!["Dependency", "Version", "Copies", "Size", "Dragged in by"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.0
+            .iter()
+            .flat_map(|d| {
+                d.versions.iter().map(|v| {
+                    vec![
+                        d.name.clone(),
+                        v.version.clone(),
+                        v.copies.to_string(),
+                        v.size.map(size).unwrap_or_else(|| "-".to_string()),
+                        join(&v.dependents, "\n"),
+                    ]
+                })
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        let copies: usize = self.0.iter().map(|d| d.copies).sum();
+        let bytes: u64 = self.0.iter().filter_map(|d| d.size).sum();
+
+        match (self.0.len(), bytes) {
+            (0, _) => "No package is installed in more than one version".to_string(),
+            (total, 0) => format!("Total : {} (copies: {})", total, copies),
+            (total, bytes) => format!(
+                "Total : {} (copies: {}, size: {})",
+                total,
+                copies,
+                size(bytes)
+            ),
+        }
+    }
+
+    fn sort(&mut self, sort: Sort, reverse: bool) {
+        format::sort(&mut self.0, sort, reverse);
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        self.0
+            .iter()
+            .map(|d| {
+                let versions = d.versions.iter().map(|v| &v.version);
+
+                Finding {
+                    rule: "transitive-duplicate",
+                    level: Level::Warning,
+                    title: format!("Transitive duplicate: {}", d.name),
+                    message: format!(
+                        "{} is installed {} times in versions {}",
+                        d.name,
+                        d.copies,
+                        join(versions, ", ")
+                    ),
+                    location: None,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Sortable for Chain {
+    fn name(&self) -> &str {
+        &self.workspace
+    }
+
+    fn count(&self) -> usize {
+        self.path.len()
+    }
+
+    fn versions(&self) -> usize {
+        1
+    }
+
+    fn severity(&self) -> Option<Level> {
+        None
+    }
+}
+
+/// Result of the `why` command
+#[derive(Debug, Serialize, Clone)]
+pub struct Why {
+    pub package: String,
+
+    /// Resolved versions of the package
+    pub versions: BTreeSet<String>,
+
+    pub chains: Vec<Chain>,
+}
+
+impl Why {
+    pub fn new(package: String, chains: Vec<Chain>) -> Self {
+        let versions = chains.iter().map(|c| c.version().to_string()).collect();
+
+        Self {
+            package,
+            versions,
+            chains,
+        }
+    }
+}
+
+impl Render for Why {
+    fn title(&self) -> &'static str {
+        "why"
+    }
+
+    fn header(&self) -> Vec<&'static str> {
+        vec!["Workspace", "Section", "Version", "Chain"]
+    }
+
+    fn rows(&self) -> Vec<Vec<String>> {
+        self.chains
+            .iter()
+            .map(|chain| {
+                let path = chain
+                    .path
+                    .iter()
+                    .map(|l| format!("{}@{}", l.name, l.version))
+                    .collect::<Vec<_>>();
+
+                vec![
+                    chain.workspace.clone(),
+                    chain.section.to_string(),
+                    chain.version().to_string(),
+                    path.join(" > "),
+                ]
+            })
+            .collect()
+    }
+
+    fn summary(&self) -> String {
+        let workspaces: BTreeSet<&String> = self.chains.iter().map(|c| &c.workspace).collect();
+
+        match self.chains.len() {
+            0 => format!("{} is not installed by any workspace", self.package),
+            total => format!(
+                "Total : {} chains from {} workspaces (versions: {})",
+                total,
+                workspaces.len(),
+                join(&self.versions, ", ")
+            ),
+        }
+    }
+
+    fn sort(&mut self, sort: Sort, reverse: bool) {
+        format::sort(&mut self.chains, sort, reverse);
+    }
+}
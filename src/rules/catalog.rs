@@ -0,0 +1,485 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+use crate::log;
+use crate::models::file::{self, FileChange, FileError};
+use crate::models::package_json::PackageJson;
+use crate::models::semantic_version::VersionRange;
+use crate::models::source_map::SourceMap;
+use crate::models::workspace::{Workspace, CATALOG_PROTOCOL, DEFAULT_CATALOG};
+
+/// A dependency that should be moved to (or referenced from) the default catalog
+#[derive(Debug, Serialize, Clone)]
+pub struct CatalogSuggestion {
+    pub dependency: String,
+
+    /// Spec to store in the catalog
+    pub suggested: String,
+
+    /// Specs currently declared by the workspaces
+    pub specs: BTreeSet<String>,
+
+    /// Workspaces not referencing the catalog yet
+    pub workspaces: BTreeSet<String>,
+
+    /// The default catalog already defines the dependency
+    pub in_catalog: bool,
+}
+
+/// How the catalog version of a dependency is chosen
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum Pick {
+    /// The spec allowing the highest version
+    #[default]
+    Highest,
+    /// The spec declared by most workspaces
+    MostUsed,
+}
+
+/// Suggests catalog entries for dependencies declared with a plain range
+/// by at least `min` workspaces.
+pub fn check(
+    manifests: &[PackageJson],
+    workspace: &Workspace,
+    min: usize,
+    pick: Pick,
+) -> Vec<CatalogSuggestion> {
+    // dependency => (workspace => specs)
+    let mut usages: BTreeMap<&String, BTreeMap<&String, BTreeSet<&String>>> = BTreeMap::new();
+
+    for manifest in manifests {
+        for (name, spec) in manifest
+            .dependencies
+            .0
+            .iter()
+            .chain(manifest.dev_dependencies.0.iter())
+        {
+            // only plain semver ranges can be moved to a catalog
+            if spec.starts_with(CATALOG_PROTOCOL) || VersionRange::from_str(spec).is_err() {
+                continue;
+            }
+
+            usages
+                .entry(name)
+                .or_default()
+                .entry(&manifest.name)
+                .or_default()
+                .insert(spec);
+        }
+    }
+
+    let catalog = workspace.get_catalog(DEFAULT_CATALOG);
+
+    usages
+        .into_iter()
+        .filter(|(_, workspaces)| workspaces.len() >= min)
+        .map(|(name, workspaces)| {
+            let specs: BTreeSet<String> = workspaces
+                .values()
+                .flatten()
+                .map(|s| s.to_string())
+                .collect();
+            let existing = catalog.and_then(|c| c.get(name));
+
+            let picked = match pick {
+                Pick::Highest => highest(&specs),
+                Pick::MostUsed => {
+                    let mut counts: BTreeMap<&String, usize> = BTreeMap::new();
+
+                    for spec in workspaces.values().flatten() {
+                        *counts.entry(spec).or_default() += 1;
+                    }
+
+                    let max = counts.values().max().copied().unwrap_or_default();
+                    let candidates = counts
+                        .into_iter()
+                        .filter(|(_, count)| *count == max)
+                        .map(|(spec, _)| spec.to_string())
+                        .collect();
+
+                    highest(&candidates)
+                }
+            };
+
+            CatalogSuggestion {
+                dependency: name.clone(),
+                suggested: existing.cloned().unwrap_or(picked),
+                in_catalog: existing.is_some(),
+                workspaces: workspaces.keys().map(|w| w.to_string()).collect(),
+                specs,
+            }
+        })
+        .collect()
+}
+
+/// Picks the spec allowing the highest minimum version
+pub fn highest(specs: &BTreeSet<String>) -> String {
+    specs
+        .iter()
+        .max_by_key(|spec| {
+            VersionRange::from_str(spec)
+                .ok()
+                .and_then(|r| r.min_version())
+                .unwrap_or_default()
+        })
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Whether two ranges accept a common version, approximated by checking
+/// the lowest version of each against the other range
+fn overlaps(a: &str, b: &str) -> bool {
+    match (VersionRange::from_str(a), VersionRange::from_str(b)) {
+        (Ok(a), Ok(b)) => {
+            a.min_version().is_some_and(|v| b.satisfies(&v))
+                || b.min_version().is_some_and(|v| a.satisfies(&v))
+        }
+        _ => false,
+    }
+}
+
+/// Plans the migration of the suggested dependencies to the default catalog:
+/// missing entries are added to `pnpm-workspace.yaml` and every matching
+/// manifest entry is rewritten to `catalog:`. Specs that do not overlap the
+/// catalog version (`^17` for `^18`) are kept, with a warning.
+pub fn migrate(
+    suggestions: &[CatalogSuggestion],
+    manifests: &[PackageJson],
+    workspace_file: &Path,
+) -> file::Result<Vec<FileChange>> {
+    let mut changes = Vec::new();
+
+    let before = fs::read_to_string(workspace_file)?;
+    let entries: Vec<(&str, &str)> = suggestions
+        .iter()
+        .filter(|s| !s.in_catalog)
+        .map(|s| (s.dependency.as_str(), s.suggested.as_str()))
+        .collect();
+
+    changes.push(FileChange {
+        path: workspace_file.to_path_buf(),
+        after: add_catalog_entries(&before, &entries)?,
+        before,
+    });
+
+    for manifest in manifests {
+        let before = fs::read_to_string(&manifest.path)?;
+        let source_map = SourceMap::parse(&before);
+        let mut after = before.clone();
+
+        let mut replacements: Vec<_> = source_map
+            .entries
+            .iter()
+            .filter(|e| e.section == "dependencies" || e.section == "devDependencies")
+            .filter(|e| {
+                let suggestion = suggestions
+                    .iter()
+                    .find(|s| s.dependency == e.name && s.workspaces.contains(&manifest.name));
+                let spec = &before[e.value.start + 1..e.value.end - 1];
+
+                let suggestion = match suggestion {
+                    Some(s) if !spec.starts_with(CATALOG_PROTOCOL) => s,
+                    _ => return false,
+                };
+
+                match overlaps(spec, &suggestion.suggested) {
+                    true => true,
+                    false => {
+                        log::warn(&format!(
+                            "Keeping {} > {} {}: it does not overlap the catalog version {}",
+                            manifest.name, e.name, spec, suggestion.suggested
+                        ));
+                        false
+                    }
+                }
+            })
+            .map(|e| e.value.clone())
+            .collect();
+
+        // replace from the end so that earlier spans stay valid
+        replacements.sort_by_key(|span| std::cmp::Reverse(span.start));
+
+        for span in replacements {
+            after.replace_range(span, &format!("\"{}\"", CATALOG_PROTOCOL));
+        }
+
+        changes.push(FileChange {
+            path: manifest.path.clone(),
+            before,
+            after,
+        });
+    }
+
+    changes.retain(|c| !c.is_empty());
+    Ok(changes)
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Blank and comment lines belong to the enclosing block
+fn is_content(line: &str) -> bool {
+    !line.trim().is_empty() && !line.trim_start().starts_with('#')
+}
+
+/// End of the block opened by the line `start`: the next content line that
+/// is not indented deeper
+fn block_end(lines: &[String], start: usize) -> usize {
+    lines[start + 1..]
+        .iter()
+        .position(|l| is_content(l) && indent(l) <= indent(&lines[start]))
+        .map(|p| start + 1 + p)
+        .unwrap_or(lines.len())
+}
+
+/// The line of `key:` among the least indented lines of `range`, with what
+/// follows the colon (comments removed)
+fn find_key(lines: &[String], range: Range<usize>, key: &str) -> Option<(usize, String)> {
+    let depth = lines[range.clone()]
+        .iter()
+        .filter(|l| is_content(l))
+        .map(|l| indent(l))
+        .min()?;
+
+    range.into_iter().find_map(|i| {
+        let line = &lines[i];
+        let rest = line.trim_start().strip_prefix(key)?.strip_prefix(':')?;
+
+        match is_content(line) && indent(line) == depth {
+            true => Some((
+                i,
+                rest.split(" #").next().unwrap_or(rest).trim().to_string(),
+            )),
+            false => None,
+        }
+    })
+}
+
+fn unsupported(key: &str) -> FileError {
+    FileError::UnsupportedWorkspace(format!(
+        "`{}` is not a block mapping, add the catalog entries by hand",
+        key
+    ))
+}
+
+/// Adds entries to the default catalog: `catalogs.default` when the default
+/// catalog is defined there, the top level `catalog` mapping otherwise,
+/// created if needed. A flow mapping written on a single line is turned into
+/// a block, other layouts are left to the user.
+fn add_catalog_entries(text: &str, entries: &[(&str, &str)]) -> file::Result<String> {
+    if entries.is_empty() {
+        return Ok(text.to_string());
+    }
+
+    let value: Value = serde_yaml::from_str(text)?;
+    let in_catalogs = value.get("catalog").is_none()
+        && value
+            .get("catalogs")
+            .and_then(|c| c.get(DEFAULT_CATALOG))
+            .is_some();
+
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+
+    let header = match in_catalogs {
+        true => {
+            let (parent, _) = find_key(&lines, 0..lines.len(), "catalogs")
+                .ok_or_else(|| unsupported("catalogs"))?;
+            let end = block_end(&lines, parent);
+
+            Some(
+                find_key(&lines, parent + 1..end, DEFAULT_CATALOG)
+                    .ok_or_else(|| unsupported("catalogs"))?,
+            )
+        }
+        false => find_key(&lines, 0..lines.len(), "catalog"),
+    };
+
+    let (insert_at, prefix) = match header {
+        Some((i, rest)) => {
+            let key_indent = lines[i][..indent(&lines[i])].to_string();
+            let key = lines[i]
+                .trim_start()
+                .split(':')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+
+            // `catalog: {react: ^18}` => `catalog:` followed by the entries
+            if !rest.is_empty() {
+                let existing: Mapping = match rest.starts_with('{') {
+                    true => serde_yaml::from_str(&rest).map_err(|_| unsupported(&key))?,
+                    false => return Err(unsupported(&key)),
+                };
+
+                let mut block = Vec::new();
+                for (name, spec) in existing.iter() {
+                    match (scalar(name), scalar(spec)) {
+                        (Some(name), Some(spec)) => block.push(format!(
+                            "{}  {}: {}",
+                            key_indent,
+                            yaml_scalar(&name),
+                            yaml_scalar(&spec)
+                        )),
+                        _ => return Err(unsupported(&key)),
+                    }
+                }
+
+                lines[i] = format!("{}{}:", key_indent, key);
+                lines.splice(i + 1..i + 1, block);
+            }
+
+            // keep blank lines separating the next section
+            let mut end = block_end(&lines, i);
+            while end > i + 1 && lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+
+            let prefix = lines[i + 1..end]
+                .iter()
+                .find(|l| is_content(l))
+                .map(|l| l[..indent(l)].to_string())
+                .unwrap_or_else(|| format!("{}  ", key_indent));
+
+            (end, prefix)
+        }
+        // the key exists but is not written on a line of its own
+        None if value.get("catalog").is_some() => return Err(unsupported("catalog")),
+        None => {
+            lines.push("catalog:".to_string());
+            (lines.len(), "  ".to_string())
+        }
+    };
+
+    let new_lines = entries
+        .iter()
+        .map(|(name, spec)| format!("{}{}: {}", prefix, yaml_scalar(name), yaml_scalar(spec)));
+
+    lines.splice(insert_at..insert_at, new_lines);
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    Ok(result)
+}
+
+/// A scalar of an existing catalog as a string
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Quotes a YAML scalar when it would not be read back as a plain string,
+/// including specs that YAML reads as numbers, booleans or null (`18`, `1.2`)
+fn yaml_scalar(value: &str) -> String {
+    let plain = value
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_alphanumeric() || c == '^')
+        .unwrap_or(false)
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && matches!(
+            serde_yaml::from_str::<Value>(value),
+            Ok(Value::String(s)) if s == value
+        );
+
+    match plain {
+        true => value.to_string(),
+        false => format!("'{}'", value.replace('\'', "''")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::workspace::Workspace;
+
+    #[test]
+    fn catalog_entries_are_read_back_as_strings() {
+        let text = "packages:\n  - packages/*\n";
+        let entries = [
+            ("react", "18"),
+            ("vue", "3"),
+            ("flag", "true"),
+            ("lib", "1.2"),
+            ("none", "null"),
+            ("lodash", "^4.17.21"),
+            ("next", "14.1.0"),
+        ];
+
+        let written = add_catalog_entries(text, &entries).unwrap();
+        assert!(written.contains("  react: '18'\n"));
+        assert!(written.contains("  lodash: ^4.17.21\n"));
+        assert!(written.contains("  next: 14.1.0\n"));
+
+        let value: serde_yaml::Value = serde_yaml::from_str(&written).unwrap();
+        let workspace: Workspace = serde_yaml::from_str(&written).unwrap();
+
+        for (name, spec) in entries {
+            assert_eq!(
+                value["catalog"][name],
+                serde_yaml::Value::String(spec.to_string())
+            );
+            assert_eq!(workspace.catalog[name], spec);
+        }
+    }
+
+    fn catalog_of(text: &str) -> Workspace {
+        serde_yaml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn flow_catalog_is_turned_into_a_block() {
+        let text = "packages:\n  - packages/*\ncatalog: {react: ^18.2.0, vue: '3'}\n";
+        let written = add_catalog_entries(text, &[("lodash", "^4.17.21")]).unwrap();
+
+        assert_eq!(
+            written,
+            "packages:\n  - packages/*\ncatalog:\n  react: ^18.2.0\n  vue: '3'\n  lodash: ^4.17.21\n"
+        );
+        assert_eq!(catalog_of(&written).catalog.len(), 3);
+    }
+
+    #[test]
+    fn default_catalog_under_catalogs_is_extended() {
+        let text = "packages:\n  - packages/*\ncatalogs:\n  default:\n    react: ^18.2.0\n\n  legacy:\n    react: ^17.0.2\n";
+        let written = add_catalog_entries(text, &[("lodash", "^4.17.21")]).unwrap();
+        let workspace = catalog_of(&written);
+
+        assert!(workspace.catalog.is_empty());
+        assert_eq!(workspace.catalogs["default"]["lodash"], "^4.17.21");
+        assert_eq!(workspace.catalogs["legacy"].len(), 1);
+    }
+
+    #[test]
+    fn catalogs_that_cannot_be_edited_are_an_error() {
+        let texts = [
+            "packages: []\ncatalog: {\n  react: ^18.2.0\n}\n",
+            "packages: []\ncatalogs: {default: {react: ^18.2.0}}\n",
+        ];
+
+        for text in texts {
+            assert!(add_catalog_entries(text, &[("lodash", "^4.17.21")]).is_err());
+        }
+    }
+
+    #[test]
+    fn specs_across_majors_do_not_overlap() {
+        assert!(overlaps("^18.0.0", "^18.2.0"));
+        assert!(overlaps("~4.17.0", "^4.17.21"));
+        assert!(overlaps(">=1", "^2.0.0"));
+        assert!(!overlaps("^17.0.2", "^18.2.0"));
+        assert!(!overlaps("^0.1.0", "^0.2.0"));
+    }
+}
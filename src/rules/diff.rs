@@ -0,0 +1,262 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::analysis::Options;
+use crate::models::package_json::PackageJson;
+use crate::models::resolver::Resolver;
+use crate::models::spec::Spec;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeKind {
+    /// Declared in more than one version, it was not before
+    Duplicated,
+    /// Declared in a single version, it was not before
+    Aligned,
+    /// Still declared in more than one version, including new ones
+    NewVersion,
+    /// Declared by a workspace that did not declare it
+    Added,
+    /// No longer declared by a workspace
+    Removed,
+    /// Declared in another version by the same workspace
+    Updated,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Duplicated => "duplicated",
+            ChangeKind::Aligned => "aligned",
+            ChangeKind::NewVersion => "new version",
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Updated => "updated",
+        }
+    }
+}
+
+/// How a dependency changed between two revisions
+#[derive(Debug, Serialize, Clone)]
+pub struct DependencyChange {
+    pub name: String,
+    pub kind: ChangeKind,
+
+    /// `None` for changes involving every workspace (duplicated, aligned...)
+    pub workspace: Option<String>,
+
+    pub before: BTreeSet<String>,
+    pub after: BTreeSet<String>,
+}
+
+/// A revision of the monorepo
+pub struct Snapshot<'a> {
+    pub manifests: &'a [PackageJson],
+    pub resolver: &'a Resolver,
+}
+
+/// Dependency => workspace => versions, a workspace may declare a dependency
+/// several times (in another section or through an `npm:` alias)
+type Declarations = BTreeMap<String, BTreeMap<String, BTreeSet<String>>>;
+
+fn declarations(snapshot: &Snapshot, options: &Options) -> Declarations {
+    let mut result = Declarations::new();
+
+    for manifest in snapshot.manifests {
+        if !options.accepts_workspace(&manifest.name) {
+            continue;
+        }
+
+        for section in options.sections.iter() {
+            let mut declared: Vec<_> = manifest.section(*section).0.iter().collect();
+            declared.sort();
+
+            for (name, declared) in declared {
+                let spec = match snapshot.resolver.resolve(&manifest.name, name, declared) {
+                    Some(spec) => Spec::parse(&spec),
+                    None => continue,
+                };
+
+                let (name, spec) = match spec.dealias() {
+                    Some((real, inner)) => (real.to_string(), inner.clone()),
+                    None => (name.clone(), spec),
+                };
+
+                if !options.accepts(&name) {
+                    continue;
+                }
+
+                result
+                    .entry(name)
+                    .or_default()
+                    .entry(manifest.name.clone())
+                    .or_default()
+                    .insert(spec.version().unwrap_or_else(|| spec.to_string()));
+            }
+        }
+    }
+
+    result
+}
+
+fn versions(declarations: Option<&BTreeMap<String, BTreeSet<String>>>) -> BTreeSet<String> {
+    declarations
+        .into_iter()
+        .flat_map(|d| d.values().flatten().cloned())
+        .collect()
+}
+
+/// Compares the dependencies declared in `options.sections` of two revisions,
+/// leaving out the packages and workspaces filtered by `options`
+pub fn compare(before: &Snapshot, after: &Snapshot, options: &Options) -> Vec<DependencyChange> {
+    let before = declarations(before, options);
+    let after = declarations(after, options);
+
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut changes = Vec::new();
+
+    for name in names {
+        let (old, new) = (before.get(name), after.get(name));
+        let (old_versions, new_versions) = (versions(old), versions(new));
+
+        let kind = match (old_versions.len(), new_versions.len()) {
+            (0..=1, 2..) => Some(ChangeKind::Duplicated),
+            (2.., 1) => Some(ChangeKind::Aligned),
+            (2.., 2..) if !new_versions.is_subset(&old_versions) => Some(ChangeKind::NewVersion),
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            changes.push(DependencyChange {
+                name: name.clone(),
+                kind,
+                workspace: None,
+                before: old_versions,
+                after: new_versions,
+            });
+        }
+
+        let empty = BTreeMap::new();
+        let (old, new) = (old.unwrap_or(&empty), new.unwrap_or(&empty));
+        let workspaces: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+
+        for workspace in workspaces {
+            let (old_version, new_version) = (old.get(workspace), new.get(workspace));
+
+            let kind = match (old_version, new_version) {
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(_), None) => ChangeKind::Removed,
+                (Some(a), Some(b)) if a != b => ChangeKind::Updated,
+                _ => continue,
+            };
+
+            changes.push(DependencyChange {
+                name: name.clone(),
+                kind,
+                workspace: Some(workspace.clone()),
+                before: old_version.cloned().unwrap_or_default(),
+                after: new_version.cloned().unwrap_or_default(),
+            });
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn manifest(name: &str, dependencies: &str) -> PackageJson {
+        let text = format!(
+            r#"{{"name": "{}", "dependencies": {}}}"#,
+            name, dependencies
+        );
+        PackageJson::parse(Path::new("packages/a/package.json"), &text).unwrap()
+    }
+
+    #[test]
+    fn same_revision_with_alias_has_no_changes() {
+        let manifests = vec![manifest(
+            "a",
+            r#"{"react": "^18.2.0", "r17": "npm:react@^17.0.0", "lodash": "^4.17.21"}"#,
+        )];
+        let resolver = Resolver::default();
+        let snapshot = Snapshot {
+            manifests: &manifests,
+            resolver: &resolver,
+        };
+
+        for _ in 0..10 {
+            assert!(compare(&snapshot, &snapshot, &Options::default()).is_empty());
+        }
+    }
+
+    #[test]
+    fn alias_added_next_to_the_real_package() {
+        let resolver = Resolver::default();
+        let before = vec![manifest("a", r#"{"react": "^18.2.0"}"#)];
+        let after = vec![manifest(
+            "a",
+            r#"{"react": "^18.2.0", "r17": "npm:react@^17.0.0"}"#,
+        )];
+
+        let changes = compare(
+            &Snapshot {
+                manifests: &before,
+                resolver: &resolver,
+            },
+            &Snapshot {
+                manifests: &after,
+                resolver: &resolver,
+            },
+            &Options::default(),
+        );
+
+        let kinds: Vec<_> = changes.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::Duplicated, ChangeKind::Updated]);
+        assert_eq!(changes[1].after.len(), 2);
+    }
+
+    #[test]
+    fn filtered_packages_and_workspaces_are_left_out() {
+        let resolver = Resolver::default();
+        let before = vec![
+            manifest("a", r#"{"react": "^18.2.0", "lodash": "^4.17.21"}"#),
+            manifest("b", r#"{"react": "^18.2.0"}"#),
+        ];
+        let after = vec![
+            manifest("a", r#"{"react": "^18.2.0", "lodash": "^4.17.20"}"#),
+            manifest("b", r#"{"react": "^17.0.2"}"#),
+        ];
+
+        let (before, after) = (
+            Snapshot {
+                manifests: &before,
+                resolver: &resolver,
+            },
+            Snapshot {
+                manifests: &after,
+                resolver: &resolver,
+            },
+        );
+
+        let options = Options {
+            ignore: vec!["lodash".to_string()],
+            ..Default::default()
+        };
+        let changes = compare(&before, &after, &options);
+        assert!(changes.iter().all(|c| c.name == "react"));
+        assert_eq!(changes.len(), 2);
+
+        let options = Options {
+            ignore_workspace: vec!["b".to_string()],
+            ..Default::default()
+        };
+        let changes = compare(&before, &after, &options);
+        assert!(changes.iter().all(|c| c.name == "lodash"));
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::models::lockfile::{Lockfile, LockfileKind, Target};
+use crate::models::package_json::{PackageJson, Section};
+use crate::models::resolver::Resolver;
+use crate::models::source_map::Location;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum DriftKind {
+    /// The workspace is not in the lockfile
+    MissingImporter,
+    /// The lockfile has a workspace that no longer exists
+    StaleImporter,
+    /// The dependency is declared but not locked
+    Missing,
+    /// The dependency is locked from another spec
+    Mismatch,
+    /// The dependency is locked but no longer declared
+    Stale,
+}
+
+impl DriftKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DriftKind::MissingImporter => "missing importer",
+            DriftKind::StaleImporter => "stale importer",
+            DriftKind::Missing => "missing",
+            DriftKind::Mismatch => "mismatch",
+            DriftKind::Stale => "stale",
+        }
+    }
+}
+
+/// A difference between a manifest and the lockfile
+#[derive(Debug, Serialize, Clone)]
+pub struct Drift {
+    pub workspace: String,
+    pub kind: DriftKind,
+    pub dependency: Option<String>,
+    pub section: Option<Section>,
+
+    /// Spec in `package.json`
+    pub declared: Option<String>,
+
+    /// Spec the lockfile was generated from
+    pub locked: Option<String>,
+
+    pub location: Option<Location>,
+}
+
+impl Drift {
+    fn importer(workspace: &str, kind: DriftKind) -> Self {
+        Self {
+            workspace: workspace.to_string(),
+            kind,
+            dependency: None,
+            section: None,
+            declared: None,
+            locked: None,
+            location: None,
+        }
+    }
+}
+
+/// Sections recorded in the importers of the lockfile
+fn sections(kind: LockfileKind) -> Vec<Section> {
+    match kind {
+        LockfileKind::Npm => vec![
+            Section::Dependencies,
+            Section::DevDependencies,
+            Section::PeerDependencies,
+        ],
+        _ => vec![Section::Dependencies, Section::DevDependencies],
+    }
+}
+
+/// Compares the dependencies declared by the manifests with the importers of
+/// the lockfile, ie: what a frozen install would reject.
+///
+/// Overridden dependencies may be locked either from the declared spec or
+/// from the override.
+pub fn check(lockfile: &Lockfile, manifests: &[PackageJson], resolver: &Resolver) -> Vec<Drift> {
+    let mut drifts = Vec::new();
+    let mut paths = BTreeSet::new();
+
+    for manifest in manifests {
+        let path = lockfile.importer(&manifest.path);
+        paths.insert(path.clone());
+
+        let importer = match lockfile.importers.get(&path) {
+            Some(importer) => importer,
+            None => {
+                drifts.push(Drift::importer(&manifest.name, DriftKind::MissingImporter));
+                continue;
+            }
+        };
+
+        for section in sections(lockfile.kind) {
+            let mut declared: Vec<_> = manifest.section(section).0.iter().collect();
+            declared.sort();
+
+            for (name, spec) in declared {
+                let locked = importer.get(section, name);
+
+                // declared in several sections, the package manager locks it once
+                let elsewhere = importer.iter().any(|(s, n, _)| s != section && n == name);
+
+                let kind = match locked {
+                    None if elsewhere => continue,
+                    None => DriftKind::Missing,
+                    Some(locked) => {
+                        let resolved = resolver.resolve(&manifest.name, name, spec);
+                        let accepted = [Some(spec), resolved.as_ref()];
+
+                        match (&locked.specifier, &locked.target) {
+                            (Some(s), _) if !accepted.contains(&Some(s)) => DriftKind::Mismatch,
+                            (_, None) => DriftKind::Missing,
+                            _ => continue,
+                        }
+                    }
+                };
+
+                drifts.push(Drift {
+                    workspace: manifest.name.clone(),
+                    kind,
+                    dependency: Some(name.clone()),
+                    section: Some(section),
+                    declared: Some(spec.clone()),
+                    locked: locked.and_then(|l| l.specifier.clone()),
+                    location: manifest.locate(section, name),
+                });
+            }
+        }
+
+        for (section, name, locked) in importer.iter() {
+            // pnpm installs missing peers as dependencies (`auto-install-peers`)
+            let is_declared = [
+                Section::Dependencies,
+                Section::DevDependencies,
+                Section::PeerDependencies,
+            ]
+            .iter()
+            .any(|s| manifest.section(*s).0.contains_key(name));
+
+            if !is_declared {
+                drifts.push(Drift {
+                    workspace: manifest.name.clone(),
+                    kind: DriftKind::Stale,
+                    dependency: Some(name.clone()),
+                    section: Some(section),
+                    declared: None,
+                    locked: locked.specifier.clone(),
+                    location: None,
+                });
+            }
+        }
+    }
+
+    for path in lockfile.importers.keys() {
+        if !paths.contains(path) {
+            drifts.push(Drift::importer(path, DriftKind::StaleImporter));
+        }
+    }
+
+    // workspaces linked from the lockfile must still exist
+    let linked: BTreeSet<&String> = lockfile
+        .packages
+        .values()
+        .flat_map(|p| p.dependencies.values())
+        .filter_map(|target| match target {
+            Target::Workspace(path) => Some(path),
+            Target::Package(_) => None,
+        })
+        .collect();
+
+    for path in linked {
+        if !paths.contains(path) && !lockfile.importers.contains_key(path) {
+            drifts.push(Drift::importer(path, DriftKind::StaleImporter));
+        }
+    }
+
+    drifts
+}
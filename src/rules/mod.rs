@@ -0,0 +1,8 @@
+pub mod catalog;
+pub mod diff;
+pub mod drift;
+pub mod overrides;
+pub mod peer_drift;
+pub mod sources;
+pub mod transitive;
+pub mod why;
@@ -0,0 +1,167 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::models::lockfile::Lockfile;
+use crate::models::overrides::{Override, OverrideSource};
+use crate::models::package_json::PackageJson;
+use crate::models::semantic_version::{SemanticVersion, VersionRange};
+
+/// How an override relates to the dependencies of the monorepo
+#[derive(Debug, Serialize, Clone)]
+pub struct OverrideUsage {
+    pub source: OverrideSource,
+    pub key: String,
+    pub version: String,
+
+    /// Workspaces declaring the overridden package directly
+    pub workspaces: BTreeSet<String>,
+
+    /// Whether the lockfile holds a version matching the selector or the forced
+    /// version, or records the override itself (pnpm)
+    pub locked: bool,
+
+    /// The override does not match any dependency anymore
+    pub stale: bool,
+
+    /// Whether the usage could be determined from the manifests or the lockfile
+    pub verified: bool,
+}
+
+/// Matches every override against the declared dependencies and the packages
+/// of the lockfile, if any.
+pub fn check(
+    overrides: &[Override],
+    manifests: &[PackageJson],
+    lockfile: Option<&Lockfile>,
+) -> Vec<OverrideUsage> {
+    let mut result: Vec<OverrideUsage> = overrides
+        .iter()
+        .filter_map(|o| {
+            let target = o.target()?;
+
+            let workspaces: BTreeSet<String> = manifests
+                .iter()
+                .filter(|m| {
+                    [&m.dependencies, &m.dev_dependencies, &m.peer_dependencies]
+                        .iter()
+                        .flat_map(|deps| deps.0.iter())
+                        .any(|(name, spec)| o.applies_to(&m.name, name, spec))
+                })
+                .map(|m| m.name.clone())
+                .collect();
+
+            // once applied, a ranged override (`foo@<2: 2.0.0`) only leaves the
+            // forced version in the lockfile, which the selector does not match
+            let forced = VersionRange::from_str(&o.version).ok();
+            let recorded = o.source == OverrideSource::Pnpm
+                && lockfile.is_some_and(|l| l.overrides.contains_key(&o.key));
+
+            let locked = recorded
+                || locked_versions(lockfile, &target.name).iter().any(|v| {
+                    target.matches_version(&target.name, v)
+                        || forced.as_ref().is_some_and(|r| r.satisfies(v))
+                });
+
+            // an override scoped to a parent is only reachable through that parent
+            let parent_reachable = match o.parents().first() {
+                Some(parent) => {
+                    manifests.iter().any(|m| {
+                        m.name == parent.name
+                            || [&m.dependencies, &m.dev_dependencies]
+                                .iter()
+                                .any(|deps| deps.0.contains_key(&parent.name))
+                    }) || !locked_versions(lockfile, &parent.name).is_empty()
+                }
+                None => true,
+            };
+
+            // without a lockfile transitive packages cannot be verified
+            let stale =
+                !parent_reachable || (workspaces.is_empty() && lockfile.is_some() && !locked);
+
+            Some(OverrideUsage {
+                source: o.source,
+                key: o.key.clone(),
+                version: o.version.clone(),
+                verified: !workspaces.is_empty() || locked || stale,
+                stale,
+                workspaces,
+                locked,
+            })
+        })
+        .collect();
+
+    result.sort_by(|a, b| (&a.key, a.source).cmp(&(&b.key, b.source)));
+    result
+}
+
+/// Versions of `name` resolved by the lockfile
+fn locked_versions(lockfile: Option<&Lockfile>, name: &str) -> Vec<SemanticVersion> {
+    lockfile
+        .into_iter()
+        .flat_map(|l| l.packages.values())
+        .filter(|p| p.name == name)
+        .map(|p| SemanticVersion::from_str(&p.version).unwrap_or_default())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use super::*;
+    use crate::models::lockfile::{LockedPackage, LockfileKind};
+    use crate::models::overrides::parse_pnpm;
+
+    fn lockfile(packages: &[(&str, &str)]) -> Lockfile {
+        let mut lockfile = Lockfile::new(LockfileKind::Pnpm, Path::new("pnpm-lock.yaml"));
+
+        for (name, version) in packages {
+            let package = LockedPackage {
+                name: name.to_string(),
+                version: version.to_string(),
+                ..Default::default()
+            };
+            lockfile
+                .packages
+                .insert(format!("{}@{}", name, version), package);
+        }
+
+        lockfile
+    }
+
+    fn overrides(entries: &[(&str, &str)]) -> Vec<Override> {
+        let map: HashMap<String, String> = entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        parse_pnpm(&map, &HashMap::new())
+    }
+
+    #[test]
+    fn ranged_override_with_the_forced_version_locked_is_not_stale() {
+        let overrides = overrides(&[("minimist@<1.2.6", "1.2.6"), ("left-pad@<1", "1.3.0")]);
+        let usages = check(&overrides, &[], Some(&lockfile(&[("minimist", "1.2.6")])));
+
+        assert_eq!(usages[0].key, "left-pad@<1");
+        assert!(usages[0].stale);
+        assert_eq!(usages[1].key, "minimist@<1.2.6");
+        assert!(usages[1].locked && !usages[1].stale);
+    }
+
+    #[test]
+    fn override_recorded_by_the_lockfile_is_not_stale() {
+        let overrides = overrides(&[("foo@^1", "2.0.0")]);
+        let mut lockfile = lockfile(&[]);
+        lockfile
+            .overrides
+            .insert("foo@^1".to_string(), "2.0.0".to_string());
+
+        let usages = check(&overrides, &[], Some(&lockfile));
+        assert!(usages[0].locked && !usages[0].stale);
+    }
+}
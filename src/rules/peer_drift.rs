@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::models::package_json::{PackageJson, Section};
+use crate::models::resolver::Resolver;
+use crate::models::semantic_version::VersionRange;
+use crate::models::source_map::Location;
+
+/// A `devDependencies` entry that falls outside the workspace's own peer range
+#[derive(Debug, Serialize, Clone)]
+pub struct PeerDrift {
+    pub workspace: String,
+    pub dependency: String,
+    pub peer_range: String,
+    pub dev_version: String,
+
+    /// Where the peer dependency is declared
+    pub location: Option<Location>,
+}
+
+/// Checks that every dependency declared both as peer and as dev dependency
+/// of the given manifest is tested against a version the peer range accepts.
+///
+/// Both specs go through the resolver first (catalogs, overrides). Specs that
+/// are not semver ranges (eg: `workspace:*`, git urls) are skipped.
+pub fn check(manifest: &PackageJson, resolver: &Resolver) -> Vec<PeerDrift> {
+    let mut drifts = Vec::new();
+
+    for (name, peer) in manifest.peer_dependencies.0.iter() {
+        let dev = match manifest.dev_dependencies.0.get(name) {
+            Some(dev) => dev,
+            None => continue,
+        };
+
+        let (peer, dev) = match (
+            resolver.resolve(&manifest.name, name, peer),
+            resolver.resolve(&manifest.name, name, dev),
+        ) {
+            (Some(peer), Some(dev)) => (peer, dev),
+            _ => continue,
+        };
+
+        let (peer_range, dev_range) =
+            match (VersionRange::from_str(&peer), VersionRange::from_str(&dev)) {
+                (Ok(p), Ok(d)) => (p, d),
+                _ => continue,
+            };
+
+        let satisfied = dev_range
+            .min_version()
+            .map(|v| peer_range.satisfies(&v))
+            .unwrap_or(false);
+
+        if !satisfied {
+            drifts.push(PeerDrift {
+                workspace: manifest.name.clone(),
+                dependency: name.clone(),
+                peer_range: peer,
+                dev_version: dev,
+                location: manifest.locate(Section::PeerDependencies, name),
+            });
+        }
+    }
+
+    drifts.sort_by(|a, b| a.dependency.cmp(&b.dependency));
+    drifts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::models::workspace::Workspace;
+
+    #[test]
+    fn catalog_specs_are_resolved() {
+        let manifest = PackageJson::parse(
+            Path::new("packages/a/package.json"),
+            r#"{
+                "name": "a",
+                "peerDependencies": { "react": "catalog:" },
+                "devDependencies": { "react": "catalog:legacy" }
+            }"#,
+        )
+        .unwrap();
+
+        let workspace = Workspace {
+            catalog: [("react".to_string(), "^18.0.0".to_string())].into(),
+            catalogs: [(
+                "legacy".to_string(),
+                [("react".to_string(), "^17.0.2".to_string())].into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let drifts = check(&manifest, &Resolver::new(vec![], Some(workspace)));
+        assert_eq!(drifts.len(), 1);
+        assert_eq!(drifts[0].peer_range, "^18.0.0");
+        assert_eq!(drifts[0].dev_version, "^17.0.2");
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::models::package_json::PackageJson;
+use crate::models::resolver::Resolver;
+use crate::models::spec::{Spec, SpecKind};
+
+/// A git, tarball or path source referenced at different revisions
+#[derive(Debug, Serialize, Clone)]
+pub struct SourceConflict {
+    pub location: String,
+    pub kind: SpecKind,
+
+    /// Dependency names pointing at the source
+    pub dependencies: BTreeSet<String>,
+
+    /// Revision (`None` when unpinned) => workspaces using it
+    pub references: BTreeMap<Option<String>, BTreeSet<String>>,
+}
+
+/// Groups git, tarball and path dependencies by normalized source and reports
+/// the sources used with more than one ref, commit or tarball version.
+pub fn check(manifests: &[PackageJson], resolver: &Resolver) -> Vec<SourceConflict> {
+    let mut sources: BTreeMap<String, SourceConflict> = BTreeMap::new();
+
+    for manifest in manifests {
+        let dir = manifest.path.parent().unwrap_or(Path::new(""));
+
+        for (name, spec) in manifest
+            .dependencies
+            .0
+            .iter()
+            .chain(manifest.dev_dependencies.0.iter())
+        {
+            let spec = match resolver.resolve(&manifest.name, name, spec) {
+                Some(spec) => Spec::parse(&spec),
+                None => continue,
+            };
+
+            let source = match spec.source(dir) {
+                Some(source) => source,
+                None => continue,
+            };
+
+            let kind = spec.dealias().map(|(_, s)| s.kind()).unwrap_or(spec.kind());
+
+            let conflict =
+                sources
+                    .entry(source.location.clone())
+                    .or_insert_with(|| SourceConflict {
+                        location: source.location.clone(),
+                        kind,
+                        dependencies: BTreeSet::new(),
+                        references: BTreeMap::new(),
+                    });
+
+            conflict.dependencies.insert(name.clone());
+            conflict
+                .references
+                .entry(source.reference)
+                .or_default()
+                .insert(manifest.name.clone());
+        }
+    }
+
+    sources
+        .into_values()
+        .filter(|s| s.references.len() > 1)
+        .collect()
+}
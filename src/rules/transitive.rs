@@ -0,0 +1,161 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::models::lockfile::{Lockfile, Target};
+
+/// A version of a package installed more than once in the tree
+#[derive(Debug, Serialize, Clone)]
+pub struct InstalledVersion {
+    pub version: String,
+
+    /// Number of installed copies (install paths, peer variants...)
+    pub copies: usize,
+
+    /// Size on disk in bytes, `None` when `node_modules` is not installed
+    pub size: Option<u64>,
+
+    /// Direct dependencies pulling this version in, as `workspace > dependency`
+    pub dependents: BTreeSet<String>,
+}
+
+/// A package installed in more than one version anywhere in the tree
+#[derive(Debug, Serialize, Clone)]
+pub struct TransitiveDuplicate {
+    pub name: String,
+    pub copies: usize,
+    pub size: Option<u64>,
+    pub versions: Vec<InstalledVersion>,
+}
+
+/// Size of a directory, symlinks are not followed
+fn dir_size(path: &Path) -> u64 {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(t) if t.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Packages reachable from `target`, without crossing into other workspaces
+fn closure(lockfile: &Lockfile, target: &Target) -> BTreeSet<String> {
+    let mut reached = BTreeSet::new();
+    let mut stack = vec![target.clone()];
+
+    while let Some(node) = stack.pop() {
+        let id = match &node {
+            Target::Package(id) => id,
+            Target::Workspace(_) => continue,
+        };
+
+        if !reached.insert(id.clone()) {
+            continue;
+        }
+
+        for (_, _, dependency) in lockfile.dependencies(&node) {
+            stack.push(dependency);
+        }
+    }
+
+    reached
+}
+
+/// Packages installed in more than one version, with the direct dependencies
+/// of each workspace that drag every version in.
+///
+/// `workspaces` maps importer paths to workspace names.
+pub fn check(
+    lockfile: &Lockfile,
+    workspaces: &BTreeMap<String, String>,
+) -> Vec<TransitiveDuplicate> {
+    let mut by_name: BTreeMap<&str, BTreeMap<&str, Vec<&String>>> = BTreeMap::new();
+
+    for (id, package) in lockfile.packages.iter() {
+        by_name
+            .entry(&package.name)
+            .or_default()
+            .entry(&package.version)
+            .or_default()
+            .push(id);
+    }
+
+    by_name.retain(|_, versions| versions.len() > 1);
+
+    let wanted: BTreeSet<&String> = by_name
+        .values()
+        .flat_map(|versions| versions.values().flatten().copied())
+        .collect();
+
+    // package id => `workspace > dependency`
+    let mut dependents: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut closures: BTreeMap<Target, BTreeSet<String>> = BTreeMap::new();
+
+    for (path, importer) in lockfile.importers.iter() {
+        let workspace = workspaces.get(path).unwrap_or(path);
+
+        for (_, name, dependency) in importer.iter() {
+            let target = match &dependency.target {
+                Some(t @ Target::Package(_)) => t,
+                _ => continue,
+            };
+
+            let reached = closures
+                .entry(target.clone())
+                .or_insert_with(|| closure(lockfile, target));
+
+            for id in reached.iter().filter(|id| wanted.contains(id)) {
+                dependents
+                    .entry(id.clone())
+                    .or_default()
+                    .insert(format!("{} > {}", workspace, name));
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+
+    for (name, versions) in by_name {
+        let versions: Vec<InstalledVersion> = versions
+            .into_iter()
+            .map(|(version, ids)| {
+                let dirs: Vec<_> = ids
+                    .iter()
+                    .filter_map(|id| lockfile.install_dir(id))
+                    .collect();
+
+                InstalledVersion {
+                    version: version.to_string(),
+                    copies: ids.len(),
+                    size: (!dirs.is_empty()).then(|| dirs.iter().map(|d| dir_size(d)).sum()),
+                    dependents: ids
+                        .iter()
+                        .filter_map(|id| dependents.get(*id))
+                        .flatten()
+                        .cloned()
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let sizes: Vec<u64> = versions.iter().filter_map(|v| v.size).collect();
+
+        result.push(TransitiveDuplicate {
+            name: name.to_string(),
+            copies: versions.iter().map(|v| v.copies).sum(),
+            size: (!sizes.is_empty()).then(|| sizes.iter().sum()),
+            versions,
+        });
+    }
+
+    result
+}
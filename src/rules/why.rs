@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use serde::Serialize;
+
+use crate::models::lockfile::{Lockfile, Target};
+use crate::models::package_json::Section;
+
+/// A package in a dependency chain
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub name: String,
+    pub version: String,
+}
+
+/// How a workspace ends up installing the package
+#[derive(Debug, Serialize, Clone)]
+pub struct Chain {
+    pub workspace: String,
+
+    /// Section of the first dependency of the chain
+    pub section: Section,
+
+    /// From the direct dependency of the workspace down to the package
+    pub path: Vec<Link>,
+}
+
+impl Chain {
+    /// Resolved version of the package at the end of the chain
+    pub fn version(&self) -> &str {
+        self.path
+            .last()
+            .map(|l| l.version.as_str())
+            .unwrap_or_default()
+    }
+}
+
+/// Nodes of the graph that depend, directly or not, on a package named `name`
+fn reaching(lockfile: &Lockfile, name: &str) -> BTreeSet<Target> {
+    let mut parents: BTreeMap<Target, Vec<Target>> = BTreeMap::new();
+    let mut queue = VecDeque::new();
+
+    let nodes = lockfile
+        .packages
+        .keys()
+        .map(|id| Target::Package(id.clone()))
+        .chain(
+            lockfile
+                .importers
+                .keys()
+                .map(|p| Target::Workspace(p.clone())),
+        );
+
+    for node in nodes {
+        for (_, _, dependency) in lockfile.dependencies(&node) {
+            parents.entry(dependency).or_default().push(node.clone());
+        }
+    }
+
+    for (id, package) in lockfile.packages.iter() {
+        if package.name == name {
+            queue.push_back(Target::Package(id.clone()));
+        }
+    }
+
+    let mut reached: BTreeSet<Target> = queue.iter().cloned().collect();
+
+    while let Some(node) = queue.pop_front() {
+        for parent in parents.get(&node).into_iter().flatten() {
+            if reached.insert(parent.clone()) {
+                queue.push_back(parent.clone());
+            }
+        }
+    }
+
+    reached
+}
+
+/// Chains longer than this are not explored unless asked to
+pub const DEFAULT_DEPTH: usize = 64;
+
+struct Walk<'a> {
+    lockfile: &'a Lockfile,
+    workspaces: &'a BTreeMap<String, String>,
+    name: &'a str,
+    reached: BTreeSet<Target>,
+    depth: usize,
+}
+
+impl Walk<'_> {
+    fn link(&self, node: &Target) -> Link {
+        let (name, version) = match node {
+            Target::Workspace(p) => (
+                self.workspaces.get(p).unwrap_or(p).clone(),
+                "workspace".into(),
+            ),
+            _ => self.lockfile.describe(node),
+        };
+
+        Link { name, version }
+    }
+
+    fn is_found(&self, node: &Target) -> bool {
+        match node {
+            Target::Package(id) => self
+                .lockfile
+                .packages
+                .get(id)
+                .map(|p| p.name == self.name)
+                .unwrap_or(false),
+            Target::Workspace(_) => false,
+        }
+    }
+
+    /// Shortest chains from `start` to each package named `name`.
+    ///
+    /// Like `pnpm why`, a node is only expanded the first time it is reached,
+    /// which keeps diamond shaped graphs linear instead of listing every path.
+    fn chains(&self, start: &Target, importer: &Target) -> Vec<Vec<Link>> {
+        let mut parents: BTreeMap<Target, Option<Target>> =
+            BTreeMap::from([(importer.clone(), None), (start.clone(), None)]);
+        let mut queue = VecDeque::from([(start.clone(), 1)]);
+        let mut found = Vec::new();
+
+        while let Some((node, len)) = queue.pop_front() {
+            if self.is_found(&node) {
+                found.push(node);
+                continue;
+            }
+
+            if len >= self.depth {
+                continue;
+            }
+
+            for (_, _, dependency) in self.lockfile.dependencies(&node) {
+                if self.reached.contains(&dependency) && !parents.contains_key(&dependency) {
+                    parents.insert(dependency.clone(), Some(node.clone()));
+                    queue.push_back((dependency, len + 1));
+                }
+            }
+        }
+
+        found
+            .into_iter()
+            .map(|node| {
+                let mut path = vec![self.link(&node)];
+                let mut current = parents.get(&node).cloned().flatten();
+
+                while let Some(parent) = current {
+                    path.push(self.link(&parent));
+                    current = parents.get(&parent).cloned().flatten();
+                }
+
+                path.reverse();
+                path
+            })
+            .collect()
+    }
+}
+
+/// The shortest dependency chain from each direct dependency of the importers
+/// to each package named `name`.
+///
+/// `workspaces` maps importer paths to workspace names, `depth` limits the
+/// length of the chains.
+pub fn explain(
+    lockfile: &Lockfile,
+    workspaces: &BTreeMap<String, String>,
+    name: &str,
+    depth: usize,
+) -> Vec<Chain> {
+    let walk = Walk {
+        lockfile,
+        workspaces,
+        name,
+        reached: reaching(lockfile, name),
+        depth,
+    };
+
+    let mut result = Vec::new();
+
+    for (path, importer) in lockfile.importers.iter() {
+        let workspace = workspaces.get(path).unwrap_or(path);
+
+        for (section, _, dependency) in importer.iter() {
+            let target = match &dependency.target {
+                Some(t) if walk.reached.contains(t) => t,
+                _ => continue,
+            };
+
+            let chains = walk.chains(target, &Target::Workspace(path.clone()));
+
+            result.extend(chains.into_iter().map(|path| Chain {
+                workspace: workspace.clone(),
+                section,
+                path,
+            }));
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::models::lockfile::{Importer, LockedDependency, LockedPackage, LockfileKind};
+
+    fn package(name: &str, dependencies: &[&str]) -> LockedPackage {
+        LockedPackage {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: dependencies
+                .iter()
+                .map(|d| (d.to_string(), Target::Package(format!("{}@1.0.0", d))))
+                .collect(),
+        }
+    }
+
+    /// `layers` diamonds stacked on each other: `l<n>` depends on `l<n>a` and
+    /// `l<n>b`, which both depend on `l<n+1>`
+    fn diamonds(layers: usize) -> Lockfile {
+        let mut lockfile = Lockfile::new(LockfileKind::Pnpm, Path::new("pnpm-lock.yaml"));
+
+        for i in 0..layers {
+            let next = format!("l{}", i + 1);
+            let (a, b) = (format!("l{}a", i), format!("l{}b", i));
+
+            for id in [&a, &b] {
+                lockfile
+                    .packages
+                    .insert(format!("{}@1.0.0", id), package(id, &[&next]));
+            }
+
+            let current = format!("l{}", i);
+            lockfile
+                .packages
+                .insert(format!("{}@1.0.0", current), package(&current, &[&a, &b]));
+        }
+
+        let last = format!("l{}", layers);
+        lockfile
+            .packages
+            .insert(format!("{}@1.0.0", last), package(&last, &["target"]));
+        lockfile
+            .packages
+            .insert("target@1.0.0".into(), package("target", &[]));
+
+        let mut importer = Importer::default();
+        importer
+            .dependencies
+            .entry(Section::Dependencies)
+            .or_default()
+            .insert(
+                "l0".into(),
+                LockedDependency {
+                    specifier: Some("^1.0.0".into()),
+                    target: Some(Target::Package("l0@1.0.0".into())),
+                },
+            );
+        lockfile.importers.insert(".".into(), importer);
+
+        lockfile
+    }
+
+    #[test]
+    fn diamonds_are_expanded_once() {
+        let lockfile = diamonds(22);
+        let started = Instant::now();
+
+        let chains = explain(&lockfile, &BTreeMap::new(), "target", DEFAULT_DEPTH);
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains[0].path.len(), 22 * 2 + 2);
+        assert_eq!(chains[0].version(), "1.0.0");
+    }
+
+    #[test]
+    fn depth_limits_the_chains() {
+        let lockfile = diamonds(3);
+
+        assert!(explain(&lockfile, &BTreeMap::new(), "target", 7).is_empty());
+        assert_eq!(explain(&lockfile, &BTreeMap::new(), "target", 8).len(), 1);
+    }
+}